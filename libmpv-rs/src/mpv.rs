@@ -555,6 +555,12 @@ impl Mpv {
         self.set_property("pause", false)
     }
 
+    /// Interrupt a `wait_event` call on another thread. If no thread is waiting, the next
+    /// `wait_event` call returns immediately.
+    pub fn wakeup(&self) {
+        unsafe { libmpv_sys::mpv_wakeup(self.ctx.as_ptr()) }
+    }
+
     // --- Seek functions ---
     //
 
@@ -0,0 +1,104 @@
+#![doc = include_str!("../README.md")]
+
+mod config;
+mod platform;
+
+use std::{fmt::Debug, time::Duration};
+
+pub use config::*;
+pub use platform::{Error, MediaControls};
+
+/// The status of media playback.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MediaPlayback {
+    Stopped,
+    Paused { progress: Option<MediaPosition> },
+    Playing { progress: Option<MediaPosition> },
+}
+
+/// The metadata of a media item.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MediaMetadata<'a> {
+    pub title: Option<&'a str>,
+    pub album: Option<&'a str>,
+    pub artist: Option<&'a str>,
+    pub cover_url: Option<&'a str>,
+    pub duration: Option<Duration>,
+    /// Only available on MPRIS
+    pub album_artist: Option<&'a str>,
+    /// Only available on MPRIS
+    pub track_number: Option<i32>,
+}
+
+/// Events sent by the OS media controls.
+#[derive(Clone, PartialEq, Debug)]
+pub enum MediaControlEvent {
+    Play,
+    Pause,
+    Toggle,
+    Next,
+    Previous,
+    Stop,
+
+    /// Seek forward or backward by an undetermined amount.
+    Seek(SeekDirection),
+    /// Seek forward or backward by a certain amount.
+    SeekBy(SeekDirection, Duration),
+    /// Set the position/progress of the currently playing media item.
+    SetPosition(MediaPosition),
+    /// Sets the volume. The value is intended to be from 0.0 to 1.0.
+    /// But other values are also accepted. **It is up to the user to
+    /// set constraints on this value.**
+    /// **NOTE**: If the volume event was received and correctly handled,
+    /// the user must call [`MediaControls::set_volume`]. Note that 
+    /// this must be done only with the MPRIS backend.
+    SetVolume(f64),
+    /// Open the URI in the media player.
+    OpenUri(String),
+    /// Turn shuffling on or off. (Only available on MPRIS)
+    SetShuffle(bool),
+    /// Set the loop/repeat mode. (Only available on MPRIS)
+    SetLoopStatus(LoopStatus),
+
+    /// Bring the media player's user interface to the front using any appropriate mechanism available.
+    Raise,
+    /// Shut down the media player.
+    Quit,
+}
+
+/// The loop/repeat mode of the player.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LoopStatus {
+    /// Playback stops when there are no more tracks to play.
+    #[default]
+    None,
+    /// The current track will start again from the beginning once it has finished playing.
+    Track,
+    /// The playback loops through a list of tracks.
+    Playlist,
+}
+
+/// An instant in a media item.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MediaPosition(pub Duration);
+
+/// The direction to seek in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SeekDirection {
+    Forward,
+    Backward,
+}
+
+impl Drop for MediaControls {
+    fn drop(&mut self) {
+        // Ignores errors if there are any.
+        self.detach().ok();
+    }
+}
+
+impl Debug for MediaControls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MediaControls")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    LoopStatus, MediaControlEvent, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig,
+};
+
+/// A platform-specific error.
+#[derive(Debug)]
+pub struct Error;
+
+/// A handle to OS media controls.
+pub struct MediaControls;
+
+impl MediaControls {
+    /// Create media controls with the specified config.
+    pub fn new(_config: PlatformConfig) -> Result<Self, Error> {
+        Ok(Self)
+    }
+
+    /// Attach the media control events to a handler.
+    pub fn attach<F>(&mut self, _event_handler: F) -> Result<(), Error>
+    where
+        F: Fn(MediaControlEvent) + Send + 'static,
+    {
+        Ok(())
+    }
+
+    /// Detach the event handler.
+    pub fn detach(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Set the current playback status.
+    pub fn set_playback(&mut self, _playback: MediaPlayback) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Set the metadata of the currently playing media item.
+    pub fn set_metadata(&mut self, _metadata: MediaMetadata) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Set the volume level (0.0 - 1.0) (Not supported by this backend)
+    pub fn set_volume(&mut self, _volume: f64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Tell the controllers the position jumped, e.g. after seeking (Not supported by this backend)
+    pub fn emit_seeked(&mut self, _position: MediaPosition) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Set whether playback is shuffled (Not supported by this backend)
+    pub fn set_shuffle(&mut self, _shuffle: bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Set the loop/repeat mode (Not supported by this backend)
+    pub fn set_loop_status(&mut self, _loop_status: LoopStatus) -> Result<(), Error> {
+        Ok(())
+    }
+}
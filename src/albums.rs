@@ -0,0 +1,270 @@
+use crate::keyboard::AlbumsSection;
+use crate::tui::{App, Song};
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+        Paragraph
+    },
+    prelude::*,
+    widgets::*,
+};
+
+use rand::seq::SliceRandom;
+use std::cmp::Reverse;
+
+/// How the Albums tab is sorted, cycled with O
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AlbumSort {
+    #[default]
+    Name,
+    Year,
+    DateAdded,
+    AlbumArtist,
+}
+
+impl AlbumSort {
+    /// Name -> year -> date added -> album artist -> name
+    pub fn next(self) -> Self {
+        match self {
+            AlbumSort::Name => AlbumSort::Year,
+            AlbumSort::Year => AlbumSort::DateAdded,
+            AlbumSort::DateAdded => AlbumSort::AlbumArtist,
+            AlbumSort::AlbumArtist => AlbumSort::Name,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AlbumSort::Name => "name",
+            AlbumSort::Year => "year",
+            AlbumSort::DateAdded => "date added",
+            AlbumSort::AlbumArtist => "album artist",
+        }
+    }
+}
+
+impl App {
+
+    /// Fetch every album for the Albums tab
+    pub async fn refresh_albums(&mut self) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        if let Ok(albums) = client.albums().await {
+            self.albums = albums;
+        }
+        self.sort_albums();
+    }
+
+    /// Sort the albums by `album_sort`, keeping the cursor on the same album
+    pub fn sort_albums(&mut self) {
+        let selected_id = self
+            .albums
+            .get(self.selected_album.selected().unwrap_or(0))
+            .map(|album| album.id.clone());
+        let artist = |album: &crate::client::Album| {
+            album.album_artists.first().map(|artist| artist.name.to_lowercase()).unwrap_or_default()
+        };
+        match self.album_sort {
+            AlbumSort::Name => self.albums.sort_by_key(|album| album.name.to_lowercase()),
+            // newest first
+            AlbumSort::Year => self.albums.sort_by_key(|album| Reverse(album.production_year)),
+            AlbumSort::DateAdded => self.albums.sort_by(|a, b| b.date_created.cmp(&a.date_created)),
+            AlbumSort::AlbumArtist => self.albums.sort_by_key(|album| (artist(album), album.name.to_lowercase())),
+        }
+        let index = self.albums.iter().position(|album| Some(&album.id) == selected_id.as_ref());
+        self.selected_album.select(match index {
+            Some(index) => Some(index),
+            None if self.albums.is_empty() => None,
+            None => Some(0),
+        });
+    }
+
+    /// Fetch the songs of the selected album
+    pub async fn open_album(&mut self) {
+        let album_id = match self.albums.get(self.selected_album.selected().unwrap_or(0)) {
+            Some(album) => album.id.clone(),
+            None => return,
+        };
+        let client = match self.client.as_ref() {
+            Some(client) => client,
+            None => return,
+        };
+        self.album_tracks = client.album_tracks(&album_id).await.unwrap_or_default();
+        self.open_album_id = album_id;
+        self.selected_album_track.select(if self.album_tracks.is_empty() { None } else { Some(0) });
+    }
+
+    /// Open the album under the cursor, unless it already is, so the album actions apply to it
+    pub async fn open_selected_album(&mut self) {
+        let selected = self.albums.get(self.selected_album.selected().unwrap_or(0));
+        if selected.is_some_and(|album| album.id != self.open_album_id) {
+            self.open_album().await;
+        }
+    }
+
+    /// Play the open album from a song, in order or shuffled
+    pub async fn play_album(&mut self, skip: usize, shuffle: bool) {
+        let client = match self.client.as_ref() {
+            Some(client) => client,
+            None => return,
+        };
+        let mut songs = self
+            .album_tracks
+            .iter()
+            .skip(skip)
+            .map(|track| Song::from_track(track, client))
+            .collect::<Vec<Song>>();
+        if songs.is_empty() {
+            return;
+        }
+        if shuffle {
+            songs.shuffle(&mut rand::thread_rng());
+        }
+        self.playlist = songs;
+        let _ = self.replace_playlist(); // TODO: inform user of error
+    }
+
+    pub fn render_albums(&mut self, app_container: Rect, frame: &mut Frame) {
+        let outer_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(76),
+                Constraint::Percentage(24),
+            ])
+            .split(app_container);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
+            .split(outer_layout[0]);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(45),
+                Constraint::Percentage(55),
+            ])
+            .split(left[0]);
+
+        let (albums_highlight_style, tracks_highlight_style) = match self.albums_section {
+            AlbumsSection::Albums => (
+                Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD),
+                Style::default().bg(Color::DarkGray).fg(Color::Black).add_modifier(Modifier::BOLD),
+            ),
+            AlbumsSection::Tracks => (
+                Style::default().bg(Color::DarkGray).fg(Color::Black).add_modifier(Modifier::BOLD),
+                Style::default().bg(Color::White).fg(Color::Black).add_modifier(Modifier::BOLD),
+            ),
+        };
+
+        let instructions = Title::from(Line::from(vec![
+            " Open ".white().into(),
+            "<Enter>".blue().bold(),
+            " Play ".white().into(),
+            "<a>".blue().bold(),
+            " Shuffle ".white().into(),
+            "<S>".blue().bold(),
+            " Enqueue ".white().into(),
+            "<e/E>".blue().bold(),
+            " Sort ".white().into(),
+            "<O> ".blue().bold(),
+        ]));
+
+        let albums_block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(if self.albums_section == AlbumsSection::Albums { style::Color::Blue } else { style::Color::White })
+            .title(format!("Albums ({}) - by {}", self.albums.len(), self.album_sort.title()))
+            .title(instructions.alignment(Alignment::Center).position(Position::Bottom));
+
+        if self.albums.is_empty() {
+            let message = match self.client.as_ref() {
+                Some(client) if client.offline => "Albums cannot be browsed while offline",
+                _ => "No albums found",
+            };
+            let message_paragraph = Paragraph::new(message)
+                .block(albums_block.padding(Padding::new(0, 0, columns[0].height / 2, 0)))
+                .wrap(Wrap { trim: false })
+                .alignment(Alignment::Center);
+            frame.render_widget(message_paragraph, columns[0]);
+        } else {
+            let items = self
+                .albums
+                .iter()
+                .map(|album| {
+                    let color = if album.id == self.open_album_id { Color::Blue } else { Color::White };
+                    let mut item = Text::default();
+                    item.push_span(Span::styled(album.name.as_str(), Style::default().fg(color)));
+                    if let Some(artist) = album.album_artists.first() {
+                        item.push_span(Span::styled(
+                            format!(" - {}", artist.name),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    if album.production_year > 0 {
+                        item.push_span(Span::styled(
+                            format!(" ({})", album.production_year),
+                            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                        ));
+                    }
+                    ListItem::new(item)
+                })
+                .collect::<Vec<ListItem>>();
+            let list = List::new(items)
+                .block(albums_block)
+                .highlight_symbol(">>")
+                .highlight_style(albums_highlight_style)
+                .scroll_padding(10)
+                .repeat_highlight_symbol(true);
+            frame.render_stateful_widget(list, columns[0], &mut self.selected_album);
+        }
+
+        let items = self
+            .album_tracks
+            .iter()
+            .map(|track| {
+                let color = if track.id == self.active_song_id { Color::Blue } else { Color::White };
+                // track.run_time_ticks is in microseconds
+                let seconds = (track.run_time_ticks / 10_000_000) % 60;
+                let minutes = track.run_time_ticks / 10_000_000 / 60;
+                let mut item = Text::default();
+                item.push_span(Span::styled(
+                    format!("{}. ", track.index_number),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+                item.push_span(Span::styled(track.name.as_str(), Style::default().fg(color)));
+                item.push_span(Span::styled(
+                    format!("  {}:{:02}", minutes, seconds),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+                ListItem::new(item)
+            })
+            .collect::<Vec<ListItem>>();
+        let title = match self.albums.iter().find(|album| album.id == self.open_album_id) {
+            Some(album) => format!("Tracks - {}", album.name),
+            None => String::from("Tracks"),
+        };
+        let list = List::new(items)
+            .block(
+                Block::new()
+                    .borders(Borders::ALL)
+                    .border_style(if self.albums_section == AlbumsSection::Tracks { style::Color::Blue } else { style::Color::White })
+                    .title(title),
+            )
+            .highlight_symbol(">>")
+            .highlight_style(tracks_highlight_style)
+            .scroll_padding(10)
+            .repeat_highlight_symbol(true);
+        frame.render_stateful_widget(list, columns[1], &mut self.selected_album_track);
+
+        self.render_player(frame, left[1]);
+
+        self.render_library_right(frame, outer_layout[1]);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::client::{Artist, Discography};
+
+use dirs::cache_dir;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A playback event that could not reach the server, replayed once it is reachable again
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingEvent {
+    pub endpoint: String, // relative to /Sessions, e.g. Playing or Playing/Stopped
+    pub item_id: String,
+    pub position_ticks: u64,
+}
+
+/// Snapshot of the library, so it can be browsed when the server is unreachable
+fn library_dir() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("jellyfin-tui").join("library"))
+}
+
+fn read_json<T: DeserializeOwned>(path: PathBuf) -> Option<T> {
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn write_json<T: Serialize + ?Sized>(path: PathBuf, value: &T) {
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string(value) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+pub fn save_artists(artists: &[Artist]) {
+    if let Some(dir) = library_dir() {
+        write_json(dir.join("artists.json"), artists);
+    }
+}
+
+pub fn load_artists() -> Option<Vec<Artist>> {
+    read_json(library_dir()?.join("artists.json"))
+}
+
+/// What the snapshot was taken from. `date_created` is the newest item on the server at the time
+#[derive(Serialize, Deserialize, Default)]
+struct LibraryState {
+    server: String,
+    date_created: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedDiscography {
+    date_created: String, // `LibraryState::date_created` when this was fetched
+    #[serde(default)]
+    stale: bool, // the server said some of it changed
+    discography: Discography,
+}
+
+fn load_state() -> LibraryState {
+    library_dir()
+        .and_then(|dir| read_json(dir.join("state.json")))
+        .unwrap_or_default()
+}
+
+/// Checks the snapshot against the server. A different server throws the snapshot away,
+/// a newer item marks every cached discography as stale
+pub fn validate(server: &str, date_created: &str) {
+    let dir = match library_dir() {
+        Some(dir) => dir,
+        None => return,
+    };
+    let state = load_state();
+    if state.server != server {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    if state.server != server || state.date_created != date_created {
+        write_json(dir.join("state.json"), &LibraryState {
+            server: server.to_string(),
+            date_created: date_created.to_string(),
+        });
+    }
+}
+
+pub fn save_discography(artist_id: &str, discography: Discography) {
+    if let Some(dir) = library_dir() {
+        write_json(dir.join("discographies").join(format!("{}.json", artist_id)), &CachedDiscography {
+            date_created: load_state().date_created,
+            stale: false,
+            discography,
+        });
+    }
+}
+
+/// The cached discography, and whether nothing was added to the library since it was fetched
+pub fn load_discography(artist_id: &str) -> Option<(Discography, bool)> {
+    let mut cached: CachedDiscography = read_json(library_dir()?.join("discographies").join(format!("{}.json", artist_id)))?;
+    // older snapshots have placeholder songs for album headers, and no favorite albums
+    let count = cached.discography.items.len();
+    cached.discography.items.retain(|song| song.id != "_album_");
+    let fresh = !cached.stale && cached.date_created == load_state().date_created && cached.discography.items.len() == count;
+    Some((cached.discography, fresh))
+}
+
+/// Marks the cached discographies that hold any of `item_ids` as stale, or all of them when
+/// `item_ids` is None. An id can be a song, an album or the artist. Returns the artists marked
+pub fn invalidate_discographies(item_ids: Option<&[String]>) -> Vec<String> {
+    let dir = match library_dir() {
+        Some(dir) => dir.join("discographies"),
+        None => return vec![],
+    };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut marked = vec![];
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let artist_id = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(stem) => stem.to_string(),
+            None => continue,
+        };
+        let mut cached: CachedDiscography = match read_json(path.clone()) {
+            Some(cached) => cached,
+            None => continue,
+        };
+        let affected = match item_ids {
+            None => true,
+            Some(ids) => {
+                ids.contains(&artist_id)
+                    || cached.discography.items.iter().any(|song| ids.contains(&song.id) || ids.contains(&song.album_id))
+            }
+        };
+        if affected {
+            if !cached.stale {
+                cached.stale = true;
+                write_json(path, &cached);
+            }
+            marked.push(artist_id);
+        }
+    }
+    marked
+}
+
+/// The id this install reports to the server, made up on first start. The server keys sessions and
+/// access tokens by it, so two installs must never share one
+pub fn device_id() -> String {
+    let file = cache_dir().map(|dir| dir.join("jellyfin-tui").join("device_id"));
+    if let Some(id) = file.as_ref().and_then(|file| std::fs::read_to_string(file).ok()) {
+        if !id.trim().is_empty() {
+            return id.trim().to_string();
+        }
+    }
+    let id = format!("{:032x}", rand::random::<u128>());
+    if let Some(file) = file {
+        if let Some(dir) = file.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(file, &id);
+    }
+    id
+}
+
+fn pending_events_file() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("jellyfin-tui").join("pending_events.json"))
+}
+
+pub fn push_pending_event(event: PendingEvent) {
+    let file = match pending_events_file() {
+        Some(file) => file,
+        None => return,
+    };
+    let mut events: Vec<PendingEvent> = read_json(file.clone()).unwrap_or_default();
+    events.push(event);
+    write_json(file, &events);
+}
+
+/// Removes and returns all pending events, oldest first
+pub fn take_pending_events() -> Vec<PendingEvent> {
+    let file = match pending_events_file() {
+        Some(file) => file,
+        None => return vec![],
+    };
+    let events = read_json(file.clone()).unwrap_or_default();
+    let _ = std::fs::remove_file(file);
+    events
+}
+
+/// Cover art, one file per album and image tag. The least recently shown covers are
+/// removed once the directory grows past `COVERS_MAX_BYTES`
+const COVERS_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+fn covers_dir() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("jellyfin-tui").join("covers"))
+}
+
+pub fn cover_file(album_id: &str, tag: &str) -> Option<PathBuf> {
+    // the tag changes whenever the image does, so a stale cover is never found
+    covers_dir().map(|dir| dir.join(format!("{}_{}", album_id, tag)))
+}
+
+/// The cached cover of an album, marked as just used
+pub fn load_cover(album_id: &str, tag: &str) -> Option<Vec<u8>> {
+    let path = cover_file(album_id, tag)?;
+    let bytes = std::fs::read(&path).ok()?;
+    if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+        let _ = file.set_modified(std::time::SystemTime::now());
+    }
+    Some(bytes)
+}
+
+pub fn save_cover(album_id: &str, tag: &str, bytes: &[u8]) {
+    let (dir, path) = match (covers_dir(), cover_file(album_id, tag)) {
+        (Some(dir), Some(path)) => (dir, path),
+        _ => return,
+    };
+    let _ = std::fs::create_dir_all(&dir);
+    if std::fs::write(&path, bytes).is_err() {
+        return;
+    }
+
+    let mut files = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), metadata.modified().ok()?, metadata.len()))
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    // older covers of the same album are never shown again
+    let prefix = format!("{}_", album_id);
+    files.retain(|(file, _, _)| {
+        let outdated = *file != path && file.file_name().is_some_and(|name| name.to_string_lossy().starts_with(&prefix));
+        if outdated {
+            let _ = std::fs::remove_file(file);
+        }
+        !outdated
+    });
+    files.sort_by_key(|(_, modified, _)| *modified);
+    let mut total = files.iter().map(|(_, _, len)| len).sum::<u64>();
+    for (file, _, len) in files {
+        if total <= COVERS_MAX_BYTES {
+            break;
+        }
+        if file != path {
+            let _ = std::fs::remove_file(&file);
+            total -= len;
+        }
+    }
+}
@@ -1,28 +1,104 @@
 use reqwest;
 
+use crate::cache::{self, PendingEvent};
+use crate::downloads;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_yaml;
 use dirs::config_dir;
 use dirs::cache_dir;
 use std::io::Write;
-use std::path::PathBuf;
-use std::io::Cursor;
 use std::error::Error;
 use chrono::NaiveDate;
 use std::fs::File;
 use std::io::Read;
 use std::fs::OpenOptions;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Client {
     pub base_url: String,
     http_client: reqwest::Client,
     pub access_token: String,
     user_id: String,
+    pub device_id: String, // generated once per install, the server tells sessions apart by it
+    pub offline: bool, // the server was unreachable, only the local snapshot and downloads are used
+    credentials: Credentials,
+    pub transcoding: Transcoding,
+    pub track_columns: Vec<String>, // optional track list columns, from the `track_columns` list of the config file
+    pub normalization: Normalization,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How songs are streamed, from the `transcoding` section of the config file
+#[derive(Debug, Clone)]
+pub struct Transcoding {
+    pub enabled: bool, // false = direct play
+    pub bitrate: u64, // kbps
+    pub codec: String,
+}
+
+/// Loudness normalization, from the `normalization` section of the config file
+#[derive(Debug, Clone)]
+pub struct Normalization {
+    pub mode: NormalizationMode,
+    pub preamp: f64, // dB, added to every gain
+    pub clipping_protection: bool, // never raise the volume above the original
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NormalizationMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+}
+
+impl NormalizationMode {
+    pub fn from_config(name: &str) -> Self {
+        match name {
+            "track" => NormalizationMode::Track,
+            "album" => NormalizationMode::Album,
+            _ => NormalizationMode::Off,
+        }
+    }
+
+    /// Off -> track -> album -> off
+    pub fn next(self) -> Self {
+        match self {
+            NormalizationMode::Off => NormalizationMode::Track,
+            NormalizationMode::Track => NormalizationMode::Album,
+            NormalizationMode::Album => NormalizationMode::Off,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            NormalizationMode::Off => "off",
+            NormalizationMode::Track => "track",
+            NormalizationMode::Album => "album",
+        }
+    }
+}
+
+impl Normalization {
+    /// The gain in dB to play a song at, or None if it should play untouched.
+    /// Either mode falls back to the other gain when the server has not computed the one it wants
+    pub fn gain(&self, track_gain: Option<f64>, album_gain: Option<f64>) -> Option<f64> {
+        let gain = match self.mode {
+            NormalizationMode::Off => return None,
+            NormalizationMode::Track => track_gain.or(album_gain)?,
+            NormalizationMode::Album => album_gain.or(track_gain)?,
+        } + self.preamp;
+        // the server does not report peaks, so the only gain that cannot clip is one that does not boost
+        if self.clipping_protection {
+            Some(gain.min(0.0))
+        } else {
+            Some(gain)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credentials {
     #[serde(rename = "Username")]
     username: String,
@@ -144,13 +220,32 @@ impl Client {
             }
         };
 
+        let transcoding = Transcoding {
+            enabled: d["transcoding"]["enabled"].as_bool().unwrap_or(false),
+            bitrate: d["transcoding"]["bitrate"].as_u64().unwrap_or(320),
+            codec: d["transcoding"]["codec"].as_str().unwrap_or("opus").to_string(),
+        };
+
+        let normalization = Normalization {
+            mode: NormalizationMode::from_config(d["normalization"]["mode"].as_str().unwrap_or("off")),
+            preamp: d["normalization"]["preamp"].as_f64().unwrap_or(0.0),
+            clipping_protection: d["normalization"]["clipping_protection"].as_bool().unwrap_or(true),
+        };
+
+        let track_columns = match d["track_columns"].as_array() {
+            Some(columns) => columns.iter().filter_map(|c| c.as_str().map(String::from)).collect(),
+            None => vec![String::from("favorite")],
+        };
+
+        let device_id = cache::device_id();
+
         println!("[OK] Using {} as the server.", server);
 
         let url: String = String::new() + server + "/Users/authenticatebyname";
         let response = http_client
             .post(url)
             .header("Content-Type", "text/json")
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&device_id))
             .json(&serde_json::json!({
                 "Username": _credentials.username,
                 "Pw": _credentials.password,
@@ -158,8 +253,11 @@ impl Client {
             .send()
             .await;
 
-        // TODO: some offline state handling. Implement when adding offline caching
         match response {
+            Ok(json) if json.status().is_server_error() => {
+                println!("[!!] Server error ({}), starting in offline mode", json.status());
+                Self::offline(server, http_client, device_id, _credentials, transcoding, track_columns, normalization)
+            }
             Ok(json) => {
                 let value = match json.json::<Value>().await {
                     Ok(v) => v,
@@ -181,11 +279,95 @@ impl Client {
                     http_client,
                     access_token: access_token.to_string(),
                     user_id: user_id.to_string(),
+                    device_id,
+                    offline: false,
+                    credentials: _credentials,
+                    transcoding,
+                    track_columns,
+                    normalization,
                 }
             },
             Err(e) => {
-                println!("[!!] Error authenticating: {}", e);
-                std::process::exit(1);
+                println!("[!!] Could not reach the server ({}), starting in offline mode", e);
+                Self::offline(server, http_client, device_id, _credentials, transcoding, track_columns, normalization)
+            }
+        }
+    }
+
+    fn offline(
+        server: &str,
+        http_client: reqwest::Client,
+        device_id: String,
+        credentials: Credentials,
+        transcoding: Transcoding,
+        track_columns: Vec<String>,
+        normalization: Normalization,
+    ) -> Self {
+        Self {
+            base_url: server.to_string(),
+            http_client,
+            access_token: String::from(""),
+            user_id: String::from(""),
+            device_id,
+            offline: true,
+            credentials,
+            transcoding,
+            track_columns,
+            normalization,
+        }
+    }
+
+    /// Tries to authenticate again after starting offline. Returns true once the server is back
+    ///
+    pub async fn reconnect(&mut self) -> bool {
+        let url = format!("{}/Users/authenticatebyname", self.base_url);
+        let response = self.http_client
+            .post(url)
+            .header("Content-Type", "text/json")
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .json(&serde_json::json!({
+                "Username": self.credentials.username,
+                "Pw": self.credentials.password,
+            }))
+            .send()
+            .await;
+
+        let value = match response {
+            Ok(json) => match json.json::<Value>().await {
+                Ok(value) => value,
+                Err(_) => return false,
+            },
+            Err(_) => return false,
+        };
+        match (value["AccessToken"].as_str(), value["User"]["Id"].as_str()) {
+            (Some(access_token), Some(user_id)) => {
+                self.access_token = access_token.to_string();
+                self.user_id = user_id.to_string();
+                self.offline = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Sends the playback events recorded while offline, keeping the ones that still fail
+    ///
+    pub async fn replay_pending_events(&self) {
+        for event in cache::take_pending_events() {
+            let url = format!("{}/Sessions/{}", self.base_url, event.endpoint);
+            let response = self.http_client
+                .post(url)
+                .header("X-MediaBrowser-Token", self.access_token.to_string())
+                .header("x-emby-authorization", authorization(&self.device_id))
+                .header("Content-Type", "application/json")
+                .json(&serde_json::json!({
+                    "ItemId": event.item_id,
+                    "PositionTicks": event.position_ticks
+                }))
+                .send()
+                .await;
+            if response.is_err() {
+                cache::push_pending_event(event);
             }
         }
     }
@@ -193,12 +375,15 @@ impl Client {
     /// Produces a list of artists, called by the main function before initializing the app
     ///
     pub async fn artists(&self, search_term: String) -> Result<Vec<Artist>, reqwest::Error> {
+        if self.offline {
+            return Ok(cache::load_artists().unwrap_or_default());
+        }
         let url = format!("{}/Artists", self.base_url);
 
         let response: Result<reqwest::Response, reqwest::Error> = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "text/json")
             .query(&[
                 ("SearchTerm", search_term.as_str()),
@@ -229,15 +414,64 @@ impl Client {
         Ok(artists.items)
     }
 
+    /// Produces all artists with the recently added ones marked, and saves them as the local snapshot
+    ///
+    pub async fn library_artists(&self) -> Result<Vec<Artist>, reqwest::Error> {
+        let mut artists = self.artists(String::from("")).await?;
+        if self.offline {
+            return Ok(artists);
+        }
+
+        let new_artists = self.new_artists().await.unwrap_or_default();
+        for artist in &mut artists {
+            if new_artists.contains(&artist.id) {
+                artist.jellyfintui_recently_added = true;
+            }
+        }
+
+        if !artists.is_empty() {
+            cache::save_artists(&artists);
+        }
+        Ok(artists)
+    }
+
+    /// Produces the DateCreated of the newest song in the library, used to tell if the local snapshot is outdated
+    ///
+    pub async fn latest_date_created(&self) -> Result<String, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("SortBy", "DateCreated"),
+                ("SortOrder", "Descending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "Audio"),
+                ("Fields", "DateCreated"),
+                ("Limit", "1"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let value = response.json::<Value>().await?;
+        Ok(value["Items"][0]["DateCreated"].as_str().unwrap_or("").to_string())
+    }
+
     /// Produces a list of songs by an artist sorted by album and index
     ///
     pub async fn discography(&self, id: &str) -> Result<Discography, reqwest::Error> {
+        if self.offline {
+            return Ok(Self::offline_discography(id));
+        }
         let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
 
         let response = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "text/json")
             .query(&[
                 ("SortBy", "Album"),
@@ -255,9 +489,7 @@ impl Client {
 
         let discog = match response {
             Ok(json) => {
-                let discog: Discography = json.json().await.unwrap_or_else(|_| Discography {
-                    items: vec![],
-                });
+                let discog: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
 
                 // group the songs by album
                 let mut albums: Vec<DiscographyAlbum> = vec![];
@@ -295,36 +527,35 @@ impl Client {
                     album.songs.sort_by(|a, b| a.parent_index_number.cmp(&b.parent_index_number));
                 }
 
-                // now we flatten the albums back into a list of songs
-                let mut last_album_name = "".to_string();
+                // now we flatten the albums back into a list of songs, grouped by album
                 let mut songs: Vec<DiscographySong> = vec![];
                 for album in albums.iter() {
-                    if album.songs.len() == 0 {
-                        continue;
-                    }
-                    // push a dummy song with the album name
-                    let mut album_song = album.songs[0].clone();
-                    // let name be Artist - Album - Year
-                    album_song.name = format!("{} ({})", album.songs[0].album, album.songs[0].production_year);
-                    album_song.id = String::from("_album_");
-                    album_song.album_artists = album.songs[0].album_artists.clone();
-                    album_song.album_id = "".to_string();
-                    album_song.album_artists = vec![];
-                    if album.songs[0].album != last_album_name {
-                        songs.push(album_song);
-                        last_album_name = album.songs[0].album.clone();
-                    }
-
-                    for song in album.songs.iter() {
-                        songs.push(song.clone());
+                    songs.extend(album.songs.iter().cloned());
+                }
+                let artist_albums = self.artist_albums(id).await;
+                for song in songs.iter_mut() {
+                    if let Some(album) = artist_albums.iter().find(|album| album.id == song.album_id) {
+                        song.album_normalization_gain = album.normalization_gain;
                     }
                 }
+                let favorite_album_ids = artist_albums
+                    .into_iter()
+                    .filter(|album| album.user_data.is_favorite)
+                    .map(|album| album.id)
+                    .collect::<Vec<String>>();
+
+                if !songs.is_empty() {
+                    cache::save_discography(id, Discography {
+                        items: songs.clone(),
+                        favorite_album_ids: favorite_album_ids.clone(),
+                    });
+                }
 
                 // now we've seen this artist, so let's mark it in the cache
                 let cache_dir = match cache_dir() {
                     Some(dir) => dir,
                     None => {
-                        return Ok(Discography { items: songs });
+                        return Ok(Discography { items: songs, favorite_album_ids });
                     }
                 };
 
@@ -339,20 +570,108 @@ impl Client {
                         }
                     },
                     Err(_) => {
-                        return Ok(Discography { items: songs });
+                        return Ok(Discography { items: songs, favorite_album_ids });
                     }
                 }
 
-                Discography { items: songs }
+                Discography { items: songs, favorite_album_ids }
             },
             Err(_) => {
-                return Ok(Discography { items: vec![] });
+                return Ok(Discography::default());
             }
         };
 
         return Ok(discog);
     }
 
+    /// The snapshot of an artist's discography, cut down to the downloaded songs
+    fn offline_discography(id: &str) -> Discography {
+        let mut snapshot = match cache::load_discography(id) {
+            Some((discography, _)) => discography,
+            None => Discography::default(),
+        };
+        snapshot.items.retain(|song| downloads::downloaded_file(&song.id).is_some());
+        snapshot
+    }
+
+    /// Produces the songs of an album sorted by disc and index
+    ///
+    pub async fn album_tracks(&self, album_id: &str) -> Result<Vec<DiscographySong>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("SortBy", "ParentIndexNumber,IndexNumber,SortName"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "Audio"),
+                ("Fields", "Genres, DateCreated, MediaSources, ParentId"),
+                ("ImageTypeLimit", "1"),
+                ("ParentId", album_id)
+            ])
+            .query(&[("StartIndex", "0")])
+            .send()
+            .await;
+
+        let mut songs = match response {
+            Ok(json) => {
+                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
+                songs.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        if let Some(album) = self.album(album_id).await {
+            for song in songs.iter_mut() {
+                song.album_normalization_gain = album.normalization_gain;
+            }
+        }
+
+        Ok(songs)
+    }
+
+    /// Produces every album in the library, for the Albums tab. They are sorted locally
+    ///
+    pub async fn albums(&self) -> Result<Vec<Album>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("SortBy", "SortName"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "MusicAlbum"),
+                ("Fields", "DateCreated"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let albums = match response {
+            Ok(json) => {
+                let albums: SearchAlbums = json.json().await.unwrap_or_else(|_| SearchAlbums {
+                    items: vec![],
+                });
+                albums.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(albums)
+    }
+
     /// This for the search functionality, it will poll albums based on the search term
     ///
     pub async fn search_albums(&self, search_term: String) -> Result<Vec<Album>, reqwest::Error> {
@@ -361,7 +680,7 @@ impl Client {
         let response = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "text/json")
             .query(&[
                 ("searchTerm", search_term.as_str()),
@@ -403,7 +722,7 @@ impl Client {
         let response = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "text/json")
             .query(&[
                 ("searchTerm", search_term.as_str()),
@@ -424,9 +743,7 @@ impl Client {
 
         let songs = match response {
             Ok(json) => {
-                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography {
-                    items: vec![],
-                });
+                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
                 // remove those where album_artists is empty
                 let songs: Vec<DiscographySong> = songs.items.into_iter().filter(|s| !s.album_artists.is_empty()).collect();
                 songs
@@ -447,7 +764,7 @@ impl Client {
         let response: Result<reqwest::Response, reqwest::Error> = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "text/json")
             .query(&[
                 ("SortBy", "DateCreated"),
@@ -523,7 +840,7 @@ impl Client {
         let response = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "application/json")
             .send()
             .await;
@@ -559,7 +876,7 @@ impl Client {
         let response = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "application/json")
             .send()
             .await?;
@@ -585,127 +902,838 @@ impl Client {
         });
     }
 
-    /// Downloads cover art for an album and saves it as cover.* in the cache_dir, filename is returned
+    /// Downloads the cover art of an album. `tag` is the album's primary image tag, which lets the server cache it
     ///
-    pub async fn download_cover_art(&self, album_id: String) -> Result<String, Box<dyn Error>> {
-        let url = format!("{}/Items/{}/Images/Primary?fillHeight=512&fillWidth=512&quality=96&tag=be2a8642e97e2151ef0580fc72f3505a", self.base_url, album_id);
+    pub async fn download_cover_art(&self, album_id: &str, tag: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut url = format!("{}/Items/{}/Images/Primary?fillHeight=512&fillWidth=512&quality=96", self.base_url, album_id);
+        if !tag.is_empty() {
+            url.push_str(&format!("&tag={}", tag));
+        }
         let response = self.http_client
             .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "application/json")
             .send()
-            .await?;
-
-        // we need to get the file extension
-        let content_type = match response.headers().get("Content-Type") {
-            Some(c) => c.to_str()?,
-            None => "",
-        };
-        let extension = match content_type {
-            "image/png" => "png",
-            "image/jpeg" => "jpeg",
-            "image/jpg" => "jpg",
-            "image/webp" => "webp",
-            _ => "png",
-        };
+            .await?
+            .error_for_status()?;
 
-        let cache_dir = match cache_dir() {
-            Some(dir) => dir,
-            None => {
-                PathBuf::from("./")
-            }
-        };
+        Ok(response.bytes().await?.to_vec())
+    }
 
-        std::fs::create_dir_all(cache_dir.join("jellyfin-tui"))?;
-        std::fs::create_dir_all(cache_dir.join("jellyfin-tui").join("covers"))?;
+    /// Streams the original file of an item, used to fill the offline cache
+    ///
+    pub async fn download(&self, id: &str) -> Result<reqwest::Response, reqwest::Error> {
+        let url = format!("{}/Items/{}/Download", self.base_url, id);
+        self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .send()
+            .await?
+            .error_for_status()
+    }
 
-        let mut file = std::fs::File::create(cache_dir.join("jellyfin-tui").join("covers").join("cover.".to_string() + extension))?;
-        let mut content =  Cursor::new(response.bytes().await?);
-        std::io::copy(&mut content, &mut file)?;
+    /// Produces the songs with the given ids, in the order of the ids
+    ///
+    pub async fn tracks(&self, ids: &[String]) -> Result<Vec<DiscographySong>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
 
-        Ok("cover.".to_string() + extension)
-    }
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("Ids", ids.join(",").as_str()),
+                ("Fields", "Genres, DateCreated, MediaSources, ParentId"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await?;
 
-    /// Produces URL of a song from its ID
-    pub fn song_url_sync(&self, song_id: String) -> String {
-        let url = format!("{}/Audio/{}/universal", self.base_url, song_id);
-        let url = url + &format!("?UserId={}&Container=opus,webm|opus,mp3,aac,m4a|aac,m4b|aac,flac,webma,webm|webma,wav,ogg&api_key={}&StartTimeTicks=0&EnableRedirection=true&EnableRemoteMedia=false", self.user_id, self.access_token);
-        url
+        let songs: Discography = response.json().await.unwrap_or_else(|_| Discography::default());
+        Ok(ids
+            .iter()
+            .filter_map(|id| songs.items.iter().find(|song| &song.id == id).cloned())
+            .collect())
     }
 
-    /// Sends a 'playing' event to the server
+    /// Tells the server this session can be remote controlled, and which commands it accepts
     ///
-    pub async fn playing(&self, song_id: String) -> Result<(), reqwest::Error> {
-        let url = format!("{}/Sessions/Playing", self.base_url);
-        let _response = self.http_client
+    pub async fn register_capabilities(&self) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Sessions/Capabilities/Full", self.base_url);
+        self.http_client
             .post(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
+            .header("x-emby-authorization", authorization(&self.device_id))
             .header("Content-Type", "application/json")
             .json(&serde_json::json!({
-                "ItemId": song_id,
-                "PositionTicks": 0
+                "PlayableMediaTypes": ["Audio"],
+                "SupportedCommands": ["SetVolume", "VolumeUp", "VolumeDown"],
+                "SupportsMediaControl": true,
+                "SupportsPersistentIdentifier": false,
             }))
             .send()
-            .await;
+            .await?
+            .error_for_status()?;
 
         Ok(())
     }
 
-    /// Sends a 'stopped' event to the server. Needed for scrobbling
+    /// The session WebSocket, which the server pushes remote control commands through
+    pub fn socket_url(&self) -> String {
+        let base_url = match self.base_url.strip_prefix("http") {
+            Some(rest) => format!("ws{}", rest), // http -> ws, https -> wss
+            None => self.base_url.clone(),
+        };
+        format!("{}/socket?api_key={}&deviceId={}", base_url, self.access_token, self.device_id)
+    }
+
+    /// The albums of an artist, for what the songs do not carry: the favorite flag and the album gain
     ///
-    pub async fn stopped(&self, song_id: String, position_ticks: u64) -> Result<(), reqwest::Error> {
-        let url = format!("{}/Sessions/Playing/Stopped", self.base_url);
-        let _response = self.http_client
-            .post(url)
+    async fn artist_albums(&self, artist_id: &str) -> Vec<Album> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
             .header("X-MediaBrowser-Token", self.access_token.to_string())
-            .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "ItemId": song_id,
-                "PositionTicks": position_ticks
-            }))
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "MusicAlbum"),
+                ("ArtistIds", artist_id)
+            ])
             .send()
             .await;
 
-        Ok(())
+        match response {
+            Ok(json) => match json.json::<SearchAlbums>().await {
+                Ok(albums) => albums.items,
+                Err(_) => vec![],
+            },
+            Err(_) => vec![],
+        }
     }
-}
 
-/// Reports progress to the server using the info we have from mpv
-/// 
-pub async fn report_progress(base_url: String, access_token: String, pr: ProgressReport) -> Result<(), reqwest::Error> {
-    let url = format!("{}/Sessions/Playing/Progress", base_url);
-    // new http client, this is a pure function so we can create a new one
-    let client = reqwest::Client::new();
-    let _response = client
-        .post(url)
-        .header("X-MediaBrowser-Token", access_token.to_string())
-        .header("x-emby-authorization", "MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"None\", Version=\"10.4.3\"")
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({
-            "VolumeLevel": pr.volume_level,
-            "IsMuted": false,
-            "IsPaused": pr.is_paused,
-            "ShuffleMode": "Sorted",
-            "PositionTicks": pr.position_ticks,
-            // "PlaybackStartTimeTicks": pr.playback_start_time_ticks,
-            "PlaybackRate": 1,
-            "SecondarySubtitleStreamIndex": -1,
-            // "BufferedRanges": [{"start": 0, "end": 1457709999.9999998}],
-            "MediaSourceId": pr.media_source_id,
-            "CanSeek": pr.can_seek,
-            "ItemId": pr.item_id,
-            "EventName": "timeupdate"
-        }))
-        .send()
-        .await;
+    /// A single album
+    ///
+    async fn album(&self, album_id: &str) -> Option<Album> {
+        let url = format!("{}/Users/{}/Items/{}", self.base_url, self.user_id, album_id);
 
-        Ok(())
-}
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .send()
+            .await;
 
-/// TYPES ///
+        match response {
+            Ok(json) => json.json::<Album>().await.ok(),
+            Err(_) => None,
+        }
+    }
+
+    /// Produces all songs the user marked as favorite, by artist and album
+    ///
+    pub async fn favorite_tracks(&self) -> Result<Vec<DiscographySong>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("SortBy", "AlbumArtist,Album,ParentIndexNumber,IndexNumber"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "Audio"),
+                ("Filters", "IsFavorite"),
+                ("Fields", "Genres, DateCreated, MediaSources, ParentId"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let songs = match response {
+            Ok(json) => {
+                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
+                songs.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(songs)
+    }
+
+    /// Produces the most recently added albums, for the dashboard
+    ///
+    pub async fn latest_albums(&self) -> Result<Vec<Album>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items/Latest", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("IncludeItemTypes", "MusicAlbum"),
+                ("Limit", "50"),
+                ("GroupItems", "true"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        // this endpoint returns a bare array instead of Items
+        let albums = match response {
+            Ok(json) => json.json::<Vec<Album>>().await.unwrap_or_default(),
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(albums)
+    }
+
+    /// Produces songs similar to an artist, album or song, starting with it. Jellyfin calls this an instant mix
+    ///
+    pub async fn instant_mix(&self, id: &str) -> Result<Vec<DiscographySong>, reqwest::Error> {
+        let url = format!("{}/Items/{}/InstantMix", self.base_url, id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("UserId", self.user_id.as_str()),
+                ("Fields", "Genres, DateCreated, MediaSources, ParentId"),
+                ("Limit", "100"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let songs = match response {
+            Ok(json) => {
+                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
+                songs.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(songs)
+    }
+
+    /// Produces played songs, most recent first for `DatePlayed` or most played first for `PlayCount`
+    ///
+    pub async fn played_tracks(&self, sort_by: &str) -> Result<Vec<DiscographySong>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("SortBy", sort_by),
+                ("SortOrder", "Descending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "Audio"),
+                ("Filters", "IsPlayed"),
+                ("Fields", "Genres, DateCreated, MediaSources, ParentId"),
+                ("Limit", "50"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let songs = match response {
+            Ok(json) => {
+                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
+                songs.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(songs)
+    }
+
+    /// Produces the albums marked as favorite
+    ///
+    pub async fn favorite_albums(&self) -> Result<Vec<Album>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("SortBy", "AlbumArtist,SortName"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "MusicAlbum"),
+                ("Filters", "IsFavorite"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let albums = match response {
+            Ok(json) => {
+                let albums: SearchAlbums = json.json().await.unwrap_or_else(|_| SearchAlbums {
+                    items: vec![],
+                });
+                albums.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(albums)
+    }
+
+    /// Produces the music genres with their song, album and artist counts
+    ///
+    pub async fn genres(&self) -> Result<Vec<Genre>, reqwest::Error> {
+        let url = format!("{}/MusicGenres", self.base_url);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("UserId", self.user_id.as_str()),
+                ("SortBy", "SortName"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("Fields", "ItemCounts")
+            ])
+            .send()
+            .await;
+
+        let genres = match response {
+            Ok(json) => {
+                let genres: Genres = json.json().await.unwrap_or_else(|_| Genres {
+                    items: vec![],
+                });
+                genres.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(genres)
+    }
+
+    /// Produces the artists of a genre
+    ///
+    pub async fn genre_artists(&self, genre_id: &str) -> Result<Vec<Artist>, reqwest::Error> {
+        let url = format!("{}/Artists", self.base_url);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("UserId", self.user_id.as_str()),
+                ("GenreIds", genre_id),
+                ("SortBy", "SortName"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("Fields", "SortName"),
+                ("ImageTypeLimit", "-1")
+            ])
+            .send()
+            .await;
+
+        let artists = match response {
+            Ok(json) => {
+                let artists: Artists = json.json().await.unwrap_or_else(|_| Artists {
+                    items: vec![],
+                    start_index: 0,
+                    total_record_count: 0,
+                });
+                artists.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(artists)
+    }
+
+    /// Produces the albums of a genre
+    ///
+    pub async fn genre_albums(&self, genre_id: &str) -> Result<Vec<Album>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("GenreIds", genre_id),
+                ("SortBy", "AlbumArtist,SortName"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "MusicAlbum"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let albums = match response {
+            Ok(json) => {
+                let albums: SearchAlbums = json.json().await.unwrap_or_else(|_| SearchAlbums {
+                    items: vec![],
+                });
+                albums.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(albums)
+    }
+
+    /// Produces up to 300 random songs of a genre
+    ///
+    pub async fn genre_tracks(&self, genre_id: &str) -> Result<Vec<DiscographySong>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("GenreIds", genre_id),
+                ("SortBy", "Random"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "Audio"),
+                ("Fields", "Genres, DateCreated, MediaSources, ParentId"),
+                ("Limit", "300"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let songs = match response {
+            Ok(json) => {
+                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
+                songs.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(songs)
+    }
+
+    /// Marks an artist, album or song as favorite, or unmarks it
+    ///
+    pub async fn set_favorite(&self, id: &str, favorite: bool) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Users/{}/FavoriteItems/{}", self.base_url, self.user_id, id);
+        let request = if favorite {
+            self.http_client.post(url)
+        } else {
+            self.http_client.delete(url)
+        };
+        request
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Produces URL of a song from its ID
+    pub fn song_url_sync(&self, song_id: String) -> String {
+        // a downloaded copy plays without the server
+        if let Some(path) = downloads::downloaded_file(&song_id) {
+            return path.to_string_lossy().to_string();
+        }
+        let url = format!("{}/Audio/{}/universal", self.base_url, song_id);
+        if self.transcoding.enabled {
+            // only the target codec may be direct played, anything else or anything above the cap is transcoded
+            let codec = self.transcoding.codec.as_str();
+            let container = match codec {
+                "opus" | "vorbis" => "ogg",
+                _ => codec,
+            };
+            return url + &format!(
+                "?UserId={}&Container={}|{}&AudioCodec={}&TranscodingContainer={}&TranscodingProtocol=http&MaxStreamingBitrate={}&api_key={}&StartTimeTicks=0&EnableRedirection=true&EnableRemoteMedia=false",
+                self.user_id, container, codec, codec, container, self.transcoding.bitrate * 1000, self.access_token
+            );
+        }
+        let url = url + &format!("?UserId={}&Container=opus,webm|opus,mp3,aac,m4a|aac,m4b|aac,flac,webma,webm|webma,wav,ogg&api_key={}&StartTimeTicks=0&EnableRedirection=true&EnableRemoteMedia=false", self.user_id, self.access_token);
+        url
+    }
+
+    /// Produces a list of the user's audio playlists
+    ///
+    pub async fn playlists(&self) -> Result<Vec<Playlist>, reqwest::Error> {
+        let url = format!("{}/Users/{}/Items", self.base_url, self.user_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("SortBy", "SortName"),
+                ("SortOrder", "Ascending"),
+                ("Recursive", "true"),
+                ("IncludeItemTypes", "Playlist"),
+                ("Fields", "ChildCount"),
+                ("ImageTypeLimit", "-1")
+            ])
+            .query(&[("StartIndex", "0")])
+            .send()
+            .await;
+
+        let playlists = match response {
+            Ok(json) => {
+                let playlists: Playlists = json.json().await.unwrap_or_else(|_| Playlists {
+                    items: vec![],
+                });
+                // jellyfin keeps video playlists in the same place
+                playlists.items.into_iter().filter(|p| p.media_type == "Audio" || p.media_type.is_empty()).collect()
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(playlists)
+    }
+
+    /// Produces the songs of a playlist in playlist order. Every song carries its playlist entry id
+    ///
+    pub async fn playlist(&self, playlist_id: &str) -> Result<Vec<DiscographySong>, reqwest::Error> {
+        let url = format!("{}/Playlists/{}/Items", self.base_url, playlist_id);
+
+        let response = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("UserId", self.user_id.as_str()),
+                ("Fields", "Genres, DateCreated, MediaSources, ParentId"),
+                ("ImageTypeLimit", "1")
+            ])
+            .send()
+            .await;
+
+        let songs = match response {
+            Ok(json) => {
+                let songs: Discography = json.json().await.unwrap_or_else(|_| Discography::default());
+                songs.items
+            },
+            Err(_) => {
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(songs)
+    }
+
+    /// Creates a new audio playlist with the given songs, returns the id of the new playlist
+    ///
+    pub async fn create_playlist(&self, name: &str, song_ids: &[String]) -> Result<String, Box<dyn Error>> {
+        let url = format!("{}/Playlists", self.base_url);
+
+        let response = self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "Name": name,
+                "Ids": song_ids,
+                "UserId": self.user_id,
+                "MediaType": "Audio"
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let created: Value = response.json().await?;
+        Ok(created["Id"].as_str().unwrap_or("").to_string())
+    }
+
+    /// Renames a playlist. Requires jellyfin 10.9
+    ///
+    pub async fn rename_playlist(&self, playlist_id: &str, name: &str) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Playlists/{}", self.base_url, playlist_id);
+
+        self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "Name": name
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Appends songs to the end of a playlist
+    ///
+    pub async fn add_to_playlist(&self, playlist_id: &str, song_ids: &[String]) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Playlists/{}/Items", self.base_url, playlist_id);
+
+        self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "application/json")
+            .query(&[
+                ("ids", song_ids.join(",").as_str()),
+                ("userId", self.user_id.as_str())
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Removes entries from a playlist. Takes playlist entry ids, not song ids
+    ///
+    pub async fn remove_from_playlist(&self, playlist_id: &str, entry_ids: &[String]) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Playlists/{}/Items", self.base_url, playlist_id);
+
+        self.http_client
+            .delete(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "application/json")
+            .query(&[("entryIds", entry_ids.join(",").as_str())])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Moves a playlist entry to a new index within the playlist
+    ///
+    pub async fn move_playlist_item(&self, playlist_id: &str, entry_id: &str, new_index: usize) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Playlists/{}/Items/{}/Move/{}", self.base_url, playlist_id, entry_id, new_index);
+
+        self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "application/json")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Produces the other sessions this user can remote control
+    ///
+    pub async fn sessions(&self) -> Result<Vec<Session>, reqwest::Error> {
+        let url = format!("{}/Sessions", self.base_url);
+
+        let sessions: Vec<Session> = self.http_client
+            .get(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "text/json")
+            .query(&[
+                ("ControllableByUserId", self.user_id.as_str()),
+                ("ActiveWithinSeconds", "960")
+            ])
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        // our own session is the one with our device id
+        Ok(sessions
+            .into_iter()
+            .filter(|session| session.supports_remote_control && session.device_id != self.device_id)
+            .collect())
+    }
+
+    /// Sends a playstate command (PlayPause, NextTrack, PreviousTrack, Stop, Seek) to another session
+    ///
+    pub async fn session_playstate(&self, session_id: &str, command: &str, seek_position_ticks: Option<u64>) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Sessions/{}/Playing/{}", self.base_url, session_id, command);
+        let mut request = self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id));
+        if let Some(ticks) = seek_position_ticks {
+            request = request.query(&[("SeekPositionTicks", ticks.to_string())]);
+        }
+        request.send().await?.error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Sends a general command (VolumeUp, VolumeDown, ...) to another session
+    ///
+    pub async fn session_command(&self, session_id: &str, command: &str) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Sessions/{}/Command/{}", self.base_url, session_id, command);
+        self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Makes another session play the given songs, starting at `start_index` and `start_position_ticks`
+    ///
+    pub async fn session_play(&self, session_id: &str, song_ids: &[String], start_index: usize, start_position_ticks: u64) -> Result<(), reqwest::Error> {
+        let url = format!("{}/Sessions/{}/Playing", self.base_url, session_id);
+        self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .query(&[
+                ("PlayCommand", "PlayNow"),
+                ("ItemIds", song_ids.join(",").as_str()),
+                ("StartIndex", start_index.to_string().as_str()),
+                ("StartPositionTicks", start_position_ticks.to_string().as_str())
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Sends a 'playing' event to the server
+    ///
+    pub async fn playing(&self, song_id: String) -> Result<(), reqwest::Error> {
+        if self.offline {
+            cache::push_pending_event(PendingEvent { endpoint: String::from("Playing"), item_id: song_id, position_ticks: 0 });
+            return Ok(());
+        }
+        let url = format!("{}/Sessions/Playing", self.base_url);
+        let response = self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "ItemId": song_id,
+                "PositionTicks": 0
+            }))
+            .send()
+            .await;
+
+        if response.is_err() {
+            cache::push_pending_event(PendingEvent { endpoint: String::from("Playing"), item_id: song_id, position_ticks: 0 });
+        }
+
+        Ok(())
+    }
+
+    /// Sends a 'stopped' event to the server. Needed for scrobbling
+    ///
+    pub async fn stopped(&self, song_id: String, position_ticks: u64) -> Result<(), reqwest::Error> {
+        if self.offline {
+            cache::push_pending_event(PendingEvent { endpoint: String::from("Playing/Stopped"), item_id: song_id, position_ticks });
+            return Ok(());
+        }
+        let url = format!("{}/Sessions/Playing/Stopped", self.base_url);
+        let response = self.http_client
+            .post(url)
+            .header("X-MediaBrowser-Token", self.access_token.to_string())
+            .header("x-emby-authorization", authorization(&self.device_id))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "ItemId": song_id,
+                "PositionTicks": position_ticks
+            }))
+            .send()
+            .await;
+
+        if response.is_err() {
+            cache::push_pending_event(PendingEvent { endpoint: String::from("Playing/Stopped"), item_id: song_id, position_ticks });
+        }
+
+        Ok(())
+    }
+}
+
+/// The x-emby-authorization header, which identifies this install to the server
+///
+pub fn authorization(device_id: &str) -> String {
+    format!("MediaBrowser Client=\"jellyfin-tui\", Device=\"jellyfin-tui\", DeviceId=\"{}\", Version=\"10.4.3\"", device_id)
+}
+
+/// Reports progress to the server using the info we have from mpv
+/// 
+pub async fn report_progress(base_url: String, access_token: String, device_id: String, pr: ProgressReport) -> Result<(), reqwest::Error> {
+    let url = format!("{}/Sessions/Playing/Progress", base_url);
+    // new http client, this is a pure function so we can create a new one
+    let client = reqwest::Client::new();
+    let _response = client
+        .post(url)
+        .header("X-MediaBrowser-Token", access_token.to_string())
+        .header("x-emby-authorization", authorization(&device_id))
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({
+            "VolumeLevel": pr.volume_level,
+            "IsMuted": false,
+            "IsPaused": pr.is_paused,
+            "RepeatMode": pr.repeat_mode,
+            "ShuffleMode": pr.shuffle_mode,
+            "PositionTicks": pr.position_ticks,
+            // "PlaybackStartTimeTicks": pr.playback_start_time_ticks,
+            "PlaybackRate": 1,
+            "SecondarySubtitleStreamIndex": -1,
+            // "BufferedRanges": [{"start": 0, "end": 1457709999.9999998}],
+            "MediaSourceId": pr.media_source_id,
+            "CanSeek": pr.can_seek,
+            "ItemId": pr.item_id,
+            "EventName": "timeupdate"
+        }))
+        .send()
+        .await;
+
+        Ok(())
+}
+
+/// TYPES ///
 ///
 /// All the jellyfin types will be defined here. These types will be used to interact with the jellyfin server.
 
@@ -731,7 +1759,7 @@ pub struct Artist {
     #[serde(rename = "Type", default)]
     type_: String,
     #[serde(rename = "UserData", default)]
-    user_data: UserData,
+    pub user_data: UserData,
     #[serde(rename = "ImageTags", default)]
     image_tags: serde_json::Value,
     #[serde(rename = "ImageBlurHashes", default)]
@@ -752,7 +1780,7 @@ pub struct UserData {
     #[serde(rename = "PlayCount")]
     play_count: u64,
     #[serde(rename = "IsFavorite")]
-    is_favorite: bool,
+    pub is_favorite: bool,
     #[serde(rename = "Played")]
     played: bool,
     #[serde(rename = "Key")]
@@ -765,10 +1793,13 @@ pub struct UserData {
 /// We query jellyfin for all songs by an artist sorted by album and sort name.
 /// Later we group them nicely by album.
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Discography {
     #[serde(rename = "Items")]
     pub items: Vec<DiscographySong>,
+    // the songs only know their own favorite flag, not their album's
+    #[serde(rename = "FavoriteAlbumIds", default)]
+    pub favorite_album_ids: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -781,11 +1812,13 @@ pub struct DiscographySongUserData {
     #[serde(rename = "PlaybackPositionTicks")]
     playback_position_ticks: u64,
     #[serde(rename = "PlayCount")]
-    play_count: u64,
+    pub play_count: u64,
     #[serde(rename = "IsFavorite")]
-    is_favorite: bool,
+    pub is_favorite: bool,
     #[serde(rename = "Played")]
-    played: bool,
+    pub played: bool,
+    #[serde(rename = "LastPlayedDate", default)]
+    pub last_played_date: Option<String>,
     #[serde(rename = "Key")]
     key: String,
 }
@@ -800,8 +1833,8 @@ pub struct DiscographySong {
     pub album_artists: Vec<Artist>,
     #[serde(rename = "AlbumId", default)]
     pub album_id: String,
-    // #[serde(rename = "AlbumPrimaryImageTag")]
-    // album_primary_image_tag: String,
+    #[serde(rename = "AlbumPrimaryImageTag", default)]
+    pub album_primary_image_tag: String,
     #[serde(rename = "ArtistItems", default)]
     pub artist_items: Vec<Artist>,
     #[serde(rename = "Artists", default)]
@@ -815,7 +1848,7 @@ pub struct DiscographySong {
     // #[serde(rename = "GenreItems")]
     // genre_items: Vec<Genre>,
     #[serde(rename = "Genres", default)]
-    genres: Vec<String>,
+    pub genres: Vec<String>,
     #[serde(rename = "HasLyrics", default)]
     pub has_lyrics: bool,
     #[serde(rename = "Id", default)]
@@ -831,13 +1864,16 @@ pub struct DiscographySong {
     // #[serde(rename = "LocationType")]
     // location_type: String,
     #[serde(rename = "MediaSources", default)]
-    media_sources: Vec<MediaSource>,
+    pub media_sources: Vec<MediaSource>,
     #[serde(rename = "MediaType", default)]
     media_type: String,
     #[serde(rename = "Name", default)]
     pub name: String,
     #[serde(rename = "NormalizationGain", default)]
-    normalization_gain: f64,
+    pub normalization_gain: Option<f64>,
+    // not sent with the songs, filled in from the album by `discography` and `album_tracks`
+    #[serde(rename = "AlbumNormalizationGain", default)]
+    pub album_normalization_gain: Option<f64>,
     // #[serde(rename = "ParentBackdropImageTags")]
     // parent_backdrop_image_tags: Vec<String>,
     // #[serde(rename = "ParentBackdropItemId")]
@@ -857,7 +1893,10 @@ pub struct DiscographySong {
     // #[serde(rename = "Type")]
     // type_: String,
     #[serde(rename = "UserData", default)]
-    user_data: DiscographySongUserData,
+    pub user_data: DiscographySongUserData,
+    // only present when the song was fetched through a playlist
+    #[serde(rename = "PlaylistItemId", default)]
+    pub playlist_item_id: String,
 }
 
 fn index_default() -> u64 {
@@ -867,11 +1906,11 @@ fn index_default() -> u64 {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MediaSource {
     #[serde(rename = "Container", default)]
-    container: String,
+    pub container: String,
     #[serde(rename = "Size", default)]
     size: u64,
     #[serde(rename = "MediaStreams", default)]
-    media_streams: Vec<MediaStream>,
+    pub media_streams: Vec<MediaStream>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -885,7 +1924,7 @@ pub struct MediaStream {
     #[serde(rename = "SampleRate", default)]
     pub sample_rate: u64,
     #[serde(rename = "Type", default)]
-    type_: String,
+    pub type_: String,
 }
 
 /// Lyrics
@@ -929,10 +1968,10 @@ pub struct ProgressReport {
     // is_muted: bool,
     #[serde(rename = "IsPaused")]
     pub is_paused: bool,
-    // #[serde(rename = "RepeatMode")]
-    // repeat_mode: String,
-    // #[serde(rename = "ShuffleMode")]
-    // shuffle_mode: String,
+    #[serde(rename = "RepeatMode")]
+    pub repeat_mode: String,
+    #[serde(rename = "ShuffleMode")]
+    pub shuffle_mode: String,
     // #[serde(rename = "MaxStreamingBitrate")]
     // max_streaming_bitrate: u64,
     #[serde(rename = "PositionTicks")]
@@ -971,6 +2010,144 @@ pub struct Album {
     pub name: String,
     #[serde(rename = "Id",default )]
     pub id: String,
-    #[serde(rename = "AlbumArtists")]
+    #[serde(rename = "AlbumArtists", default)]
     pub album_artists: Vec<Artist>,
-}
\ No newline at end of file
+    #[serde(rename = "UserData", default)]
+    pub user_data: UserData,
+    #[serde(rename = "ProductionYear", default)]
+    pub production_year: u64,
+    #[serde(rename = "DateCreated", default)]
+    pub date_created: String,
+    #[serde(rename = "NormalizationGain", default)]
+    pub normalization_gain: Option<f64>,
+}
+
+/// GENRES
+///
+/// Music genres as shown in the Genres tab
+
+#[derive(Debug, Deserialize)]
+pub struct Genres {
+    #[serde(rename = "Items", default)]
+    pub items: Vec<Genre>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Genre {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Id", default)]
+    pub id: String,
+    #[serde(rename = "SongCount", default)]
+    pub song_count: u64,
+    #[serde(rename = "AlbumCount", default)]
+    pub album_count: u64,
+}
+
+/// PLAYLISTS
+///
+/// Jellyfin playlists as shown in the Playlists tab. Their songs are fetched as DiscographySong.
+
+#[derive(Debug, Deserialize)]
+pub struct Playlists {
+    #[serde(rename = "Items", default)]
+    pub items: Vec<Playlist>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Playlist {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Id", default)]
+    pub id: String,
+    #[serde(rename = "ChildCount", default)]
+    pub child_count: u64,
+    #[serde(rename = "MediaType", default)]
+    pub media_type: String,
+}
+
+/// SESSIONS
+///
+/// Other devices playing from the same server, controlled from the Sessions tab.
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Session {
+    #[serde(rename = "Id", default)]
+    pub id: String,
+    #[serde(rename = "Client", default)]
+    pub client: String,
+    #[serde(rename = "DeviceName", default)]
+    pub device_name: String,
+    #[serde(rename = "DeviceId", default)]
+    pub device_id: String,
+    #[serde(rename = "UserName", default)]
+    pub user_name: String,
+    #[serde(rename = "SupportsRemoteControl", default)]
+    pub supports_remote_control: bool,
+    #[serde(rename = "NowPlayingItem")]
+    pub now_playing_item: Option<NowPlayingItem>,
+    #[serde(rename = "PlayState", default)]
+    pub play_state: SessionPlayState,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NowPlayingItem {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Artists", default)]
+    pub artists: Vec<String>,
+    #[serde(rename = "RunTimeTicks", default)]
+    pub run_time_ticks: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SessionPlayState {
+    #[serde(rename = "PositionTicks", default)]
+    pub position_ticks: u64,
+    #[serde(rename = "IsPaused", default)]
+    pub is_paused: bool,
+    #[serde(rename = "VolumeLevel", default)]
+    pub volume_level: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalization(mode: NormalizationMode, preamp: f64, clipping_protection: bool) -> Normalization {
+        Normalization { mode, preamp, clipping_protection }
+    }
+
+    #[test]
+    fn gain_is_none_when_off() {
+        let off = normalization(NormalizationMode::Off, 0.0, false);
+        assert_eq!(off.gain(Some(-3.0), Some(-4.0)), None);
+    }
+
+    #[test]
+    fn gain_prefers_the_mode_and_falls_back_to_the_other() {
+        let track = normalization(NormalizationMode::Track, 0.0, false);
+        assert_eq!(track.gain(Some(-3.0), Some(-4.0)), Some(-3.0));
+        assert_eq!(track.gain(None, Some(-4.0)), Some(-4.0));
+
+        let album = normalization(NormalizationMode::Album, 0.0, false);
+        assert_eq!(album.gain(Some(-3.0), Some(-4.0)), Some(-4.0));
+        assert_eq!(album.gain(Some(-3.0), None), Some(-3.0));
+        assert_eq!(album.gain(None, None), None);
+    }
+
+    #[test]
+    fn gain_adds_the_preamp() {
+        let track = normalization(NormalizationMode::Track, 2.0, false);
+        assert_eq!(track.gain(Some(-5.0), None), Some(-3.0));
+        assert_eq!(track.gain(Some(1.0), None), Some(3.0));
+    }
+
+    #[test]
+    fn clipping_protection_never_boosts() {
+        let track = normalization(NormalizationMode::Track, 2.0, true);
+        assert_eq!(track.gain(Some(-5.0), None), Some(-3.0));
+        assert_eq!(track.gain(Some(1.0), None), Some(0.0));
+        assert_eq!(track.gain(Some(-2.0), None), Some(0.0));
+    }
+}
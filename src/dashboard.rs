@@ -0,0 +1,207 @@
+use crate::client::{Album, DiscographySong};
+use crate::keyboard::DashboardSection;
+use crate::tui::App;
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+    },
+    prelude::*,
+    widgets::*,
+};
+
+impl App {
+
+    /// Fetch all four sections of the Dashboard tab at once
+    pub async fn refresh_dashboard(&mut self) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        let (latest, recent, most, favorite) = tokio::join!(
+            client.latest_albums(),
+            client.played_tracks("DatePlayed"),
+            client.played_tracks("PlayCount"),
+            client.favorite_albums(),
+        );
+        self.recently_added_albums = latest.unwrap_or_default();
+        self.recently_played_tracks = recent.unwrap_or_default();
+        self.most_played_tracks = most.unwrap_or_default();
+        self.favorite_albums = favorite.unwrap_or_default();
+
+        for (state, len) in [
+            (&mut self.selected_recently_added, self.recently_added_albums.len()),
+            (&mut self.selected_recently_played, self.recently_played_tracks.len()),
+            (&mut self.selected_most_played, self.most_played_tracks.len()),
+            (&mut self.selected_favorite_album, self.favorite_albums.len()),
+        ] {
+            let selected = state.selected().unwrap_or(0);
+            state.select(if len == 0 { None } else { Some(selected.min(len - 1)) });
+        }
+    }
+
+    /// The cursor of the active dashboard section, and how many items it has
+    pub fn dashboard_list(&mut self) -> (&mut ListState, usize) {
+        match self.dashboard_section {
+            DashboardSection::RecentlyAdded => (&mut self.selected_recently_added, self.recently_added_albums.len()),
+            DashboardSection::RecentlyPlayed => (&mut self.selected_recently_played, self.recently_played_tracks.len()),
+            DashboardSection::MostPlayed => (&mut self.selected_most_played, self.most_played_tracks.len()),
+            DashboardSection::FavoriteAlbums => (&mut self.selected_favorite_album, self.favorite_albums.len()),
+        }
+    }
+
+    /// The songs the play and enqueue keys act on: the selected album, or the selected song and everything after it
+    pub async fn dashboard_selection(&self, from_selected: bool) -> Vec<DiscographySong> {
+        let client = match self.client.as_ref() {
+            Some(client) => client,
+            None => return vec![],
+        };
+        let (albums, tracks, state): (&[Album], &[DiscographySong], _) = match self.dashboard_section {
+            DashboardSection::RecentlyAdded => (&self.recently_added_albums, &[], &self.selected_recently_added),
+            DashboardSection::FavoriteAlbums => (&self.favorite_albums, &[], &self.selected_favorite_album),
+            DashboardSection::RecentlyPlayed => (&[], &self.recently_played_tracks, &self.selected_recently_played),
+            DashboardSection::MostPlayed => (&[], &self.most_played_tracks, &self.selected_most_played),
+        };
+        let selected = state.selected().unwrap_or(0);
+        if let Some(album) = albums.get(selected) {
+            return client.album_tracks(&album.id).await.unwrap_or_default();
+        }
+        if from_selected {
+            tracks.iter().skip(selected).cloned().collect()
+        } else {
+            tracks.get(selected).cloned().into_iter().collect()
+        }
+    }
+
+    pub fn render_dashboard(&mut self, app_container: Rect, frame: &mut Frame) {
+        let outer_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(76),
+                Constraint::Percentage(24),
+            ])
+            .split(app_container);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
+            .split(outer_layout[0]);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(left[0]);
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(rows[0]);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(rows[1]);
+
+        let recently_added = album_items(&self.recently_added_albums);
+        let recently_played = self
+            .recently_played_tracks
+            .iter()
+            .map(|track| {
+                // the date part of the ISO timestamp
+                let date = track.user_data.last_played_date.as_deref().unwrap_or("").chars().take(10).collect::<String>();
+                track_item(track, &self.active_song_id, format!("  {}", date))
+            })
+            .collect::<Vec<ListItem>>();
+        let most_played = self
+            .most_played_tracks
+            .iter()
+            .map(|track| track_item(track, &self.active_song_id, format!("  {} plays", track.user_data.play_count)))
+            .collect::<Vec<ListItem>>();
+        let favorite_albums = album_items(&self.favorite_albums);
+
+        let section = self.dashboard_section;
+        let list = dashboard_list(recently_added, "Recently added", section == DashboardSection::RecentlyAdded);
+        frame.render_stateful_widget(list, top[0], &mut self.selected_recently_added);
+        let list = dashboard_list(recently_played, "Recently played", section == DashboardSection::RecentlyPlayed);
+        frame.render_stateful_widget(list, top[1], &mut self.selected_recently_played);
+        let list = dashboard_list(most_played, "Most played", section == DashboardSection::MostPlayed);
+        frame.render_stateful_widget(list, bottom[0], &mut self.selected_most_played);
+        let list = dashboard_list(favorite_albums, "Favorite albums", section == DashboardSection::FavoriteAlbums);
+        frame.render_stateful_widget(list, bottom[1], &mut self.selected_favorite_album);
+
+        self.render_player(frame, left[1]);
+
+        self.render_library_right(frame, outer_layout[1]);
+    }
+}
+
+fn track_item<'a>(track: &DiscographySong, active_song_id: &str, detail: String) -> ListItem<'a> {
+    let color = if track.id == active_song_id { Color::Blue } else { Color::White };
+    let mut item = Text::default();
+    item.push_span(Span::styled(
+        format!("{} - {}", track.name, track.album_artist),
+        Style::default().fg(color),
+    ));
+    item.push_span(Span::styled(
+        detail,
+        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+    ));
+    ListItem::new(item)
+}
+
+fn album_items(albums: &[Album]) -> Vec<ListItem<'_>> {
+    albums
+        .iter()
+        .map(|album| {
+            let mut item = Text::default();
+            item.push_span(Span::styled(album.name.as_str(), Style::default().fg(Color::White)));
+            let artists = album.album_artists.iter().map(|a| a.name.as_str()).collect::<Vec<&str>>().join(", ");
+            if !artists.is_empty() {
+                item.push_span(Span::styled(
+                    format!(" - {}", artists),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            ListItem::new(item)
+        })
+        .collect()
+}
+
+/// A dashboard section, highlighted like the library lists when active
+fn dashboard_list<'a>(items: Vec<ListItem<'a>>, title: &str, active: bool) -> List<'a> {
+    let mut block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(if active { style::Color::Blue } else { style::Color::White })
+        .title(format!("{} ({})", title, items.len()));
+    if active {
+        let instructions = Title::from(Line::from(vec![
+            " Play ".white().into(),
+            "<Enter>".blue().bold(),
+            " Enqueue ".white().into(),
+            "<e>".blue().bold(),
+            " Play next ".white().into(),
+            "<E>".blue().bold(),
+            " Next Section ".white().into(),
+            "<Tab> ".blue().bold(),
+        ]));
+        block = block.title(instructions.alignment(Alignment::Center).position(Position::Bottom));
+    }
+    List::new(items)
+        .block(block)
+        .highlight_symbol(">>")
+        .highlight_style(if active {
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD)
+        })
+        .scroll_padding(5)
+        .repeat_highlight_symbol(true)
+}
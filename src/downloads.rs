@@ -0,0 +1,356 @@
+use crate::client::{Client, DiscographySong};
+use crate::tui::{App, Song};
+
+use dirs::cache_dir;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+        Paragraph
+    },
+    prelude::*,
+    widgets::*,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DownloadStatus {
+    Queued,
+    Downloading,
+    Done,
+    Failed,
+}
+
+/// A track marked for offline use
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Download {
+    pub song: Song,
+    pub status: DownloadStatus,
+    pub size: u64, // bytes, 0 until the server tells us
+    pub downloaded: u64,
+}
+
+/// Sent by the download worker to the ui
+pub enum DownloadEvent {
+    Started { id: String, size: u64 },
+    Progress { id: String, downloaded: u64 },
+    Finished { id: String },
+    Failed { id: String },
+}
+
+/// The managed directory downloaded tracks live in, one file per track id
+pub fn downloads_dir() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("jellyfin-tui").join("downloads"))
+}
+
+/// The local copy of a track, if it has been downloaded completely
+pub fn downloaded_file(id: &str) -> Option<PathBuf> {
+    let file = downloads_dir()?.join(id);
+    if file.is_file() {
+        Some(file)
+    } else {
+        None
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        1_048_576..=1_073_741_823 => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+        _ => format!("{:.2} GB", bytes as f64 / 1_073_741_824.0),
+    }
+}
+
+/// The download worker. Fetches one track at a time, so playback keeps most of the bandwidth
+pub async fn t_downloads(client: Client, mut receiver: UnboundedReceiver<String>, sender: Sender<DownloadEvent>) {
+    while let Some(id) = receiver.recv().await {
+        let result = download_track(&client, &id, &sender).await;
+        let _ = sender.send(match result {
+            Ok(()) => DownloadEvent::Finished { id },
+            Err(_) => DownloadEvent::Failed { id },
+        });
+    }
+}
+
+/// Writes to `<id>.part` and renames it when done, so a file named after the id is always complete
+async fn download_track(
+    client: &Client,
+    id: &str,
+    sender: &Sender<DownloadEvent>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let dir = downloads_dir().ok_or("no cache dir")?;
+    std::fs::create_dir_all(&dir)?;
+
+    let mut response = client.download(id).await?;
+    let _ = sender.send(DownloadEvent::Started {
+        id: id.to_string(),
+        size: response.content_length().unwrap_or(0),
+    });
+
+    let part = dir.join(format!("{}.part", id));
+    let mut file = std::fs::File::create(&part)?;
+    let mut downloaded: u64 = 0;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+        downloaded += chunk.len() as u64;
+        let _ = sender.send(DownloadEvent::Progress { id: id.to_string(), downloaded });
+    }
+    file.flush()?;
+    std::fs::rename(&part, dir.join(id))?;
+    Ok(())
+}
+
+fn downloads_index() -> Option<PathBuf> {
+    downloads_dir().map(|dir| dir.join("index.json"))
+}
+
+/// Everything marked for download, as saved by the last session
+pub fn load_index() -> Vec<Download> {
+    downloads_index()
+        .and_then(|file| std::fs::read_to_string(file).ok())
+        .and_then(|json| serde_json::from_str::<Vec<Download>>(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Artists with at least one finished download
+pub fn downloaded_artist_ids() -> Vec<String> {
+    load_index()
+        .iter()
+        .filter(|download| download.status == DownloadStatus::Done)
+        .flat_map(|download| download.song.artist_items.iter().map(|artist| artist.id.clone()))
+        .collect()
+}
+
+impl App {
+
+    /// Load the list of downloads and resume the unfinished ones
+    pub fn init_downloads(&mut self) {
+        let client = match self.client.as_ref() {
+            Some(client) => client.clone(),
+            None => return,
+        };
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(t_downloads(client, receiver, self.download_events_sender.clone()));
+
+        self.downloads = load_index();
+        // files deleted behind our back are forgotten
+        self.downloads.retain(|download| {
+            download.status != DownloadStatus::Done || downloaded_file(&download.song.id).is_some()
+        });
+        for download in self.downloads.iter_mut() {
+            if download.status == DownloadStatus::Queued || download.status == DownloadStatus::Downloading {
+                download.status = DownloadStatus::Queued;
+                download.downloaded = 0;
+                let _ = sender.send(download.song.id.clone());
+            }
+        }
+        self.download_sender = Some(sender);
+        if !self.downloads.is_empty() {
+            self.selected_download.select(Some(0));
+        }
+    }
+
+    fn save_downloads(&self) {
+        let file = match downloads_index() {
+            Some(file) => file,
+            None => return,
+        };
+        if let Ok(json) = serde_json::to_string(&self.downloads) {
+            if let Some(dir) = file.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            let _ = std::fs::write(file, json);
+        }
+    }
+
+    /// Mark tracks for download. Failed ones are retried, the rest are skipped if already known
+    pub fn download_tracks(&mut self, tracks: Vec<DiscographySong>) {
+        let (client, sender) = match (self.client.as_ref(), self.download_sender.as_ref()) {
+            (Some(client), Some(sender)) => (client, sender),
+            _ => return,
+        };
+        for track in tracks {
+            match self.downloads.iter_mut().find(|d| d.song.id == track.id) {
+                Some(download) if download.status == DownloadStatus::Failed => {
+                    download.status = DownloadStatus::Queued;
+                    download.downloaded = 0;
+                }
+                Some(_) => continue,
+                None => {
+                    self.downloads.push(Download {
+                        song: Song::from_track(&track, client),
+                        status: DownloadStatus::Queued,
+                        size: 0,
+                        downloaded: 0,
+                    });
+                }
+            }
+            let _ = sender.send(track.id.clone());
+        }
+        if self.selected_download.selected().is_none() && !self.downloads.is_empty() {
+            self.selected_download.select(Some(0));
+        }
+        self.save_downloads();
+    }
+
+    /// Remove a track from the cache, and its file from disk
+    pub fn delete_download(&mut self, index: usize) {
+        if index >= self.downloads.len() {
+            return;
+        }
+        let download = self.downloads.remove(index);
+        if let Some(dir) = downloads_dir() {
+            let _ = std::fs::remove_file(dir.join(&download.song.id));
+            let _ = std::fs::remove_file(dir.join(format!("{}.part", download.song.id)));
+        }
+        if index >= self.downloads.len() {
+            self.selected_download.select(self.downloads.len().checked_sub(1));
+        }
+        self.save_downloads();
+    }
+
+    /// Apply progress reported by the download worker
+    pub fn handle_download_events(&mut self) {
+        let mut changed = false;
+        while let Ok(event) = self.download_events.try_recv() {
+            let id = match &event {
+                DownloadEvent::Started { id, .. }
+                | DownloadEvent::Progress { id, .. }
+                | DownloadEvent::Finished { id }
+                | DownloadEvent::Failed { id } => id.clone(),
+            };
+            let download = match self.downloads.iter_mut().find(|d| d.song.id == id) {
+                Some(download) => download,
+                None => {
+                    // deleted while it was being downloaded
+                    if let (DownloadEvent::Finished { .. }, Some(dir)) = (&event, downloads_dir()) {
+                        let _ = std::fs::remove_file(dir.join(&id));
+                    }
+                    continue;
+                }
+            };
+            match event {
+                DownloadEvent::Started { size, .. } => {
+                    download.status = DownloadStatus::Downloading;
+                    download.size = size;
+                    download.downloaded = 0;
+                }
+                DownloadEvent::Progress { downloaded, .. } => {
+                    download.downloaded = downloaded;
+                }
+                DownloadEvent::Finished { .. } => {
+                    download.status = DownloadStatus::Done;
+                    download.size = download.size.max(download.downloaded);
+                    changed = true;
+                }
+                DownloadEvent::Failed { .. } => {
+                    download.status = DownloadStatus::Failed;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.save_downloads();
+        }
+    }
+
+    pub fn render_downloads(&mut self, app_container: Rect, frame: &mut Frame) {
+        let outer_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(76),
+                Constraint::Percentage(24),
+            ])
+            .split(app_container);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
+            .split(outer_layout[0]);
+
+        let total_size: u64 = self
+            .downloads
+            .iter()
+            .filter(|d| d.status == DownloadStatus::Done)
+            .map(|d| d.size)
+            .sum();
+        let done = self.downloads.iter().filter(|d| d.status == DownloadStatus::Done).count();
+        let title = format!("Downloads - {} of {} tracks, {}", done, self.downloads.len(), format_size(total_size));
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(style::Color::Blue)
+            .title(title);
+
+        if self.downloads.is_empty() {
+            let message_paragraph = Paragraph::new("Nothing downloaded yet. Mark tracks, albums or artists with <O>")
+                .block(block.padding(Padding::new(0, 0, left[0].height / 2, 0)))
+                .wrap(Wrap { trim: false })
+                .alignment(Alignment::Center);
+            frame.render_widget(message_paragraph, left[0]);
+        } else {
+            let items = self
+                .downloads
+                .iter()
+                .map(|download| {
+                    let color = if download.song.id == self.active_song_id { Color::Blue } else { Color::White };
+                    let mut item = Text::default();
+                    item.push_span(Span::styled(
+                        format!("{} - {}", download.song.name, download.song.artist),
+                        Style::default().fg(color),
+                    ));
+                    let status = match download.status {
+                        DownloadStatus::Queued => Span::styled("  queued", Style::default().fg(Color::DarkGray)),
+                        DownloadStatus::Downloading => {
+                            // the size is unknown when the server does not send a content length
+                            let text = match (download.downloaded * 100).checked_div(download.size) {
+                                Some(percent) => format!("  {}% of {}", percent, format_size(download.size)),
+                                None => format!("  {}", format_size(download.downloaded)),
+                            };
+                            Span::styled(text, Style::default().fg(Color::Blue))
+                        }
+                        DownloadStatus::Done => Span::styled(
+                            format!("  {}", format_size(download.size)),
+                            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                        ),
+                        DownloadStatus::Failed => Span::styled("  failed", Style::default().fg(Color::Red)),
+                    };
+                    item.push_span(status);
+                    ListItem::new(item)
+                })
+                .collect::<Vec<ListItem>>();
+
+            let instructions = Title::from(Line::from(vec![
+                " Play ".white().into(),
+                "<Enter>".blue().bold(),
+                " Delete ".white().into(),
+                "<D> ".blue().bold(),
+            ]));
+            let list = List::new(items)
+                .block(block.title(instructions.alignment(Alignment::Center).position(Position::Bottom)))
+                .highlight_symbol(">>")
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::White)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .scroll_padding(10)
+                .repeat_highlight_symbol(true);
+            frame.render_stateful_widget(list, left[0], &mut self.selected_download);
+        }
+
+        self.render_player(frame, left[1]);
+
+        self.render_library_right(frame, outer_layout[1]);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::cache;
+use crate::client::Discography;
+use crate::tui::App;
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+        Paragraph
+    },
+    prelude::*,
+    widgets::*,
+};
+
+impl App {
+
+    /// Fetch the favorite songs for the Favorites tab
+    pub async fn refresh_favorites(&mut self) {
+        if let Some(client) = self.client.as_ref() {
+            if let Ok(tracks) = client.favorite_tracks().await {
+                self.favorite_tracks = tracks;
+            }
+        }
+        let selected = self.selected_favorite.selected().unwrap_or(0);
+        if self.favorite_tracks.is_empty() {
+            self.selected_favorite.select(None);
+        } else {
+            self.selected_favorite.select(Some(selected.min(self.favorite_tracks.len() - 1)));
+        }
+    }
+
+    /// Mark or unmark an artist, album or song as favorite, and update every list it shows up in
+    pub async fn set_favorite(&mut self, id: &str, favorite: bool) {
+        if id.is_empty() {
+            return;
+        }
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        if client.set_favorite(id, favorite).await.is_err() {
+            return; // TODO: inform user of error
+        }
+
+        for artist in self.artists.iter_mut().chain(self.search_result_artists.iter_mut()).filter(|a| a.id == id) {
+            artist.user_data.is_favorite = favorite;
+        }
+        for album in self
+            .search_result_albums
+            .iter_mut()
+            .chain(self.recently_added_albums.iter_mut())
+            .chain(self.favorite_albums.iter_mut())
+            .filter(|album| album.id == id)
+        {
+            album.user_data.is_favorite = favorite;
+        }
+        for track in self.tracks.iter_mut().filter(|track| track.id == id) {
+            track.user_data.is_favorite = favorite;
+        }
+        if self.tracks.iter().any(|track| track.album_id == id) {
+            self.favorite_album_ids.retain(|album_id| album_id != id);
+            if favorite {
+                self.favorite_album_ids.push(id.to_string());
+            }
+        }
+        // the cached discographies would bring the old flag back. The open one is known, the rest refetch
+        cache::invalidate_discographies(Some(&[id.to_string()]));
+        if !self.open_artist_id.is_empty() && !self.tracks.is_empty() {
+            cache::save_discography(&self.open_artist_id, Discography {
+                items: self.tracks.clone(),
+                favorite_album_ids: self.favorite_album_ids.clone(),
+            });
+        }
+        for track in self
+            .search_result_tracks
+            .iter_mut()
+            .chain(self.playlist_tracks.iter_mut())
+            .chain(self.favorite_tracks.iter_mut())
+            .chain(self.recently_played_tracks.iter_mut())
+            .chain(self.most_played_tracks.iter_mut())
+            .filter(|track| track.id == id)
+        {
+            track.user_data.is_favorite = favorite;
+        }
+        for song in self.playlist.iter_mut().filter(|song| song.id == id) {
+            song.is_favorite = favorite;
+        }
+    }
+
+    pub fn render_favorites(&mut self, app_container: Rect, frame: &mut Frame) {
+        let outer_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(76),
+                Constraint::Percentage(24),
+            ])
+            .split(app_container);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
+            .split(outer_layout[0]);
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(style::Color::Blue)
+            .title(format!("Favorites - {} tracks", self.favorite_tracks.len()));
+
+        if self.favorite_tracks.is_empty() {
+            let message_paragraph = Paragraph::new("No favorite tracks yet. Mark artists, albums and tracks with <F>")
+                .block(block.padding(Padding::new(0, 0, left[0].height / 2, 0)))
+                .wrap(Wrap { trim: false })
+                .alignment(Alignment::Center);
+            frame.render_widget(message_paragraph, left[0]);
+        } else {
+            let items = self
+                .favorite_tracks
+                .iter()
+                .map(|track| {
+                    let color = if track.id == self.active_song_id { Color::Blue } else { Color::White };
+                    let mut item = Text::default();
+                    item.push_span(Span::styled(
+                        format!("{} - {} - {}", track.name, track.album_artist, track.album),
+                        Style::default().fg(color),
+                    ));
+                    if !track.user_data.is_favorite {
+                        item.push_span(Span::styled(
+                            "  removed",
+                            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                        ));
+                    }
+                    ListItem::new(item)
+                })
+                .collect::<Vec<ListItem>>();
+
+            let instructions = Title::from(Line::from(vec![
+                " Play ".white().into(),
+                "<Enter>".blue().bold(),
+                " Play all ".white().into(),
+                "<A>".blue().bold(),
+                " Favorite ".white().into(),
+                "<F> ".blue().bold(),
+            ]));
+            let list = List::new(items)
+                .block(block.title(instructions.alignment(Alignment::Center).position(Position::Bottom)))
+                .highlight_symbol(">>")
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::White)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .scroll_padding(10)
+                .repeat_highlight_symbol(true);
+            frame.render_stateful_widget(list, left[0], &mut self.selected_favorite);
+        }
+
+        self.render_player(frame, left[1]);
+
+        self.render_library_right(frame, outer_layout[1]);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::keyboard::GenresSection;
+use crate::tui::{App, Song};
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+        Paragraph
+    },
+    prelude::*,
+    widgets::*,
+};
+
+use rand::seq::SliceRandom;
+
+impl App {
+
+    /// Fetch the genres for the Genres tab
+    pub async fn refresh_genres(&mut self) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        if let Ok(genres) = client.genres().await {
+            self.genres = genres;
+        }
+        let selected = self.selected_genre.selected().unwrap_or(0);
+        if self.genres.is_empty() {
+            self.selected_genre.select(None);
+        } else {
+            self.selected_genre.select(Some(selected.min(self.genres.len() - 1)));
+        }
+    }
+
+    /// Fetch the artists and albums of the selected genre
+    pub async fn open_genre(&mut self) {
+        let genre = match self.genres.get(self.selected_genre.selected().unwrap_or(0)) {
+            Some(genre) => genre.clone(),
+            None => return,
+        };
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        let (artists, albums) = tokio::join!(
+            client.genre_artists(&genre.id),
+            client.genre_albums(&genre.id),
+        );
+        self.genre_artists = artists.unwrap_or_default();
+        self.genre_albums = albums.unwrap_or_default();
+        self.open_genre_id = genre.id;
+        self.selected_genre_artist.select(if self.genre_artists.is_empty() { None } else { Some(0) });
+        self.selected_genre_album.select(if self.genre_albums.is_empty() { None } else { Some(0) });
+    }
+
+    /// The cursor of the active Genres column, and how many items it has
+    pub fn genres_column(&mut self) -> (&mut ListState, usize) {
+        match self.genres_section {
+            GenresSection::Genres => (&mut self.selected_genre, self.genres.len()),
+            GenresSection::Artists => (&mut self.selected_genre_artist, self.genre_artists.len()),
+            GenresSection::Albums => (&mut self.selected_genre_album, self.genre_albums.len()),
+        }
+    }
+
+    /// Replace the queue with random songs of the open genre, or the selected one if none is open
+    pub async fn shuffle_genre(&mut self) {
+        let genre_id = if self.open_genre_id.is_empty() || self.genres_section == GenresSection::Genres {
+            match self.genres.get(self.selected_genre.selected().unwrap_or(0)) {
+                Some(genre) => genre.id.clone(),
+                None => return,
+            }
+        } else {
+            self.open_genre_id.clone()
+        };
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        let mut songs = match client.genre_tracks(&genre_id).await {
+            Ok(tracks) => tracks.iter().map(|track| Song::from_track(track, client)).collect::<Vec<Song>>(),
+            Err(_) => return, // TODO: inform user of error
+        };
+        if songs.is_empty() {
+            return;
+        }
+        // the server already picks them at random, this only matters with fewer songs than the limit
+        songs.shuffle(&mut rand::thread_rng());
+        self.playlist = songs;
+        let _ = self.replace_playlist(); // TODO: inform user of error
+    }
+
+    pub fn render_genres(&mut self, app_container: Rect, frame: &mut Frame) {
+        let outer_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(76),
+                Constraint::Percentage(24),
+            ])
+            .split(app_container);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
+            .split(outer_layout[0]);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(30),
+                Constraint::Percentage(35),
+                Constraint::Percentage(35),
+            ])
+            .split(left[0]);
+
+        if self.genres.is_empty() {
+            let message = match self.client.as_ref() {
+                Some(client) if client.offline => "Genres cannot be browsed while offline",
+                _ => "No genres found",
+            };
+            let message_paragraph = Paragraph::new(message)
+                .block(
+                    Block::new()
+                        .borders(Borders::ALL)
+                        .border_style(style::Color::Blue)
+                        .title("Genres")
+                        .padding(Padding::new(0, 0, left[0].height / 2, 0)),
+                )
+                .wrap(Wrap { trim: false })
+                .alignment(Alignment::Center);
+            frame.render_widget(message_paragraph, left[0]);
+        } else {
+            let genres = self
+                .genres
+                .iter()
+                .map(|genre| {
+                    let color = if genre.id == self.open_genre_id { Color::Blue } else { Color::White };
+                    let mut item = Text::default();
+                    item.push_span(Span::styled(genre.name.as_str(), Style::default().fg(color)));
+                    item.push_span(Span::styled(
+                        format!("  {} songs, {} albums", genre.song_count, genre.album_count),
+                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                    ));
+                    ListItem::new(item)
+                })
+                .collect::<Vec<ListItem>>();
+            let artists = self
+                .genre_artists
+                .iter()
+                .map(|artist| ListItem::new(artist.name.as_str()))
+                .collect::<Vec<ListItem>>();
+            let albums = self
+                .genre_albums
+                .iter()
+                .map(|album| {
+                    let mut item = Text::default();
+                    item.push_span(Span::styled(album.name.as_str(), Style::default().fg(Color::White)));
+                    if let Some(artist) = album.album_artists.first() {
+                        item.push_span(Span::styled(
+                            format!(" - {}", artist.name),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    ListItem::new(item)
+                })
+                .collect::<Vec<ListItem>>();
+
+            let section = self.genres_section;
+            let title = format!("Genres ({})", self.genres.len());
+            let list = genres_list(genres, title, section == GenresSection::Genres);
+            frame.render_stateful_widget(list, columns[0], &mut self.selected_genre);
+            let title = format!("Artists ({})", self.genre_artists.len());
+            let list = genres_list(artists, title, section == GenresSection::Artists);
+            frame.render_stateful_widget(list, columns[1], &mut self.selected_genre_artist);
+            let title = format!("Albums ({})", self.genre_albums.len());
+            let list = genres_list(albums, title, section == GenresSection::Albums);
+            frame.render_stateful_widget(list, columns[2], &mut self.selected_genre_album);
+        }
+
+        self.render_player(frame, left[1]);
+
+        self.render_library_right(frame, outer_layout[1]);
+    }
+}
+
+/// A column of the Genres tab, highlighted like the library lists when active
+fn genres_list(items: Vec<ListItem>, title: String, active: bool) -> List {
+    let mut block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(if active { style::Color::Blue } else { style::Color::White })
+        .title(title);
+    if active {
+        let instructions = Title::from(Line::from(vec![
+            " Open ".white().into(),
+            "<Enter>".blue().bold(),
+            " Shuffle genre ".white().into(),
+            "<S>".blue().bold(),
+            " Next Section ".white().into(),
+            "<Tab> ".blue().bold(),
+        ]));
+        block = block.title(instructions.alignment(Alignment::Center).position(Position::Bottom));
+    }
+    List::new(items)
+        .block(block)
+        .highlight_symbol(">>")
+        .highlight_style(if active {
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD)
+        })
+        .scroll_padding(5)
+        .repeat_highlight_symbol(true)
+}
@@ -1,9 +1,21 @@
-use crate::tui::{App, Song};
+use crate::client::DiscographySong;
+use crate::tui::{App, Song, TrackColumn, TrackRow};
+use crate::popup::{Popup, PopupAction};
+use crate::downloads::DownloadStatus;
+use crate::syncplay;
+use crate::player::{PlayerCommand, MAX_VOLUME};
 
+use rand::seq::SliceRandom;
 use std::io;
 use std::time::Duration;
 use crossterm::event::{self, Event, KeyEvent, KeyModifiers, KeyCode};
 
+/// Keys the global handler spends on the Library tab's sections and local search. The other tabs
+/// consume the ones they don't use, or they would change the hidden Library tab
+fn is_library_navigation(code: KeyCode) -> bool {
+    matches!(code, KeyCode::Tab | KeyCode::BackTab | KeyCode::Char('h') | KeyCode::Char('l') | KeyCode::Char('/'))
+}
+
 impl App {
     /// Poll for events and handle them
     pub async fn handle_events(&mut self) -> io::Result<()> {
@@ -75,20 +87,6 @@ impl App {
         }
     }
 
-    /// Search results as a vector of IDs
-    ///
-    fn track_search_results(&self) -> Vec<String> {
-        let items = self
-            .tracks
-            .iter()
-            .filter(|track| {
-                track.name.to_lowercase().contains(&self.tracks_search_term.to_lowercase()) && track.id != "_album_"
-            })
-            .map(|track| track.id.clone())
-            .collect::<Vec<String>>();
-        return items;
-    }
-
     fn artist_search_results(&self) -> Vec<String> {
         let items = self
             .artists
@@ -143,38 +141,130 @@ impl App {
         return self.artists[selected].id.clone();
     }
 
-    fn get_id_of_selected_track(&self) -> String {
-        if self.tracks_search_term.len() > 0 {
-            let items = self.track_search_results();
-            if items.len() == 0 {
-                return String::from("");
-            }
-            let selected = self.selected_track.selected().unwrap_or(0);
-            return items[selected].clone();
+    /// Songs under the cursor in the track list. An album row stands for all of its songs
+    fn selected_tracks(&self) -> Vec<DiscographySong> {
+        match self.selected_track_row() {
+            Some(TrackRow::Album(first)) => self.album_songs(first).to_vec(),
+            Some(TrackRow::Song(i)) => vec![self.tracks[i].clone()],
+            None => vec![],
         }
-        if self.tracks.len() == 0 {
-            return String::from("");
+    }
+
+    /// The first song of the album the cursor is in
+    fn selected_album_start(&self) -> Option<usize> {
+        let album_id = match self.selected_track_row()? {
+            TrackRow::Album(first) => return Some(first),
+            TrackRow::Song(i) => &self.tracks[i].album_id,
+        };
+        self.tracks.iter().position(|track| &track.album_id == album_id)
+    }
+
+    /// All songs of the album the cursor is in
+    fn selected_album_tracks(&self) -> Vec<DiscographySong> {
+        match self.selected_album_start() {
+            Some(first) => self.album_songs(first).to_vec(),
+            None => vec![],
         }
-        let selected = self.selected_track.selected().unwrap_or(0);
-        return self.tracks[selected].id.clone();
     }
 
-    fn reposition_track_cursor(&mut self, track_id: &str) {
-        if track_id == "" {
-            if self.tracks.len() > 0 {
-                self.selected_track.select(Some(0));
-            }
-            return;
+    /// Fold or unfold the album the cursor is in, keeping the cursor on it
+    fn toggle_album_collapsed(&mut self) {
+        let album_id = match self.selected_album_start() {
+            Some(first) => self.tracks[first].album_id.clone(),
+            None => return,
+        };
+        let selected = self.selected_track_row().map(|row| self.track_row_key(row));
+        if self.collapsed_albums.contains(&album_id) {
+            self.collapsed_albums.retain(|id| id != &album_id);
+        } else {
+            self.collapsed_albums.push(album_id.clone());
         }
-        if self.tracks_search_term.len() > 0 {
-            let items = self.track_search_results();
-            if let Some(index) = items.iter().position(|id| id == track_id) {
-                self.selected_track.select(Some(index));
+        let song_id = selected.map(|(_, song_id)| song_id).unwrap_or_default();
+        self.select_track_row(&album_id, &song_id);
+    }
+
+    /// The id and favorite flag of what 'f' and 'i' act on: the selection in the library,
+    /// the selected queue entry, or else the playing song
+    fn target_item(&self) -> Option<(String, bool)> {
+        if self.active_tab == ActiveTab::Library {
+            match self.active_section {
+                ActiveSection::Artists => {
+                    let id = self.get_id_of_selected_artist();
+                    let artist = self.artists.iter().find(|artist| artist.id == id)?;
+                    return Some((id, artist.user_data.is_favorite));
+                }
+                ActiveSection::Tracks => {
+                    return match self.selected_track_row()? {
+                        TrackRow::Album(first) => {
+                            let album_id = self.tracks[first].album_id.clone();
+                            let favorite = self.favorite_album_ids.contains(&album_id);
+                            Some((album_id, favorite))
+                        }
+                        TrackRow::Song(i) => Some((self.tracks[i].id.clone(), self.tracks[i].user_data.is_favorite)),
+                    };
+                }
+                ActiveSection::Queue => {
+                    let song = self.playlist.get(self.selected_queue_item.selected()?)?;
+                    return Some((song.id.clone(), song.is_favorite));
+                }
+                ActiveSection::Lyrics => {}
             }
+        }
+        let song = self.playlist.get(self.current_playback_state.current_index.max(0) as usize)?;
+        Some((song.id.clone(), song.is_favorite))
+    }
+
+    /// The id and favorite flag of the selected search result
+    fn search_target(&self) -> Option<(String, bool)> {
+        match self.search_section {
+            SearchSection::Artists => self
+                .search_result_artists
+                .get(self.selected_search_artist.selected().unwrap_or(0))
+                .map(|artist| (artist.id.clone(), artist.user_data.is_favorite)),
+            SearchSection::Albums => self
+                .search_result_albums
+                .get(self.selected_search_album.selected().unwrap_or(0))
+                .map(|album| (album.id.clone(), album.user_data.is_favorite)),
+            SearchSection::Tracks => self
+                .search_result_tracks
+                .get(self.selected_search_track.selected().unwrap_or(0))
+                .map(|track| (track.id.clone(), track.user_data.is_favorite)),
+        }
+    }
+
+    /// Queue songs at the end of the queue, or right after the current song
+    fn enqueue(&mut self, tracks: Vec<DiscographySong>, next: bool) {
+        let songs = match &self.client {
+            Some(client) => tracks.iter().map(|track| Song::from_track(track, client)).collect::<Vec<Song>>(),
+            None => return,
+        };
+        let _ = if next {
+            self.push_next_to_playlist(songs)
+        } else {
+            self.append_to_playlist(songs)
+        }; // TODO: inform user of error
+    }
+
+    /// Replace the queue with songs, in order or shuffled
+    fn play_tracks(&mut self, mut tracks: Vec<DiscographySong>, shuffle: bool) {
+        let client = match &self.client {
+            Some(client) => client,
+            None => return,
+        };
+        if tracks.is_empty() {
             return;
         }
-        if let Some(index) = self.tracks.iter().position(|t| t.id == track_id) {
-            self.selected_track.select(Some(index));
+        if shuffle {
+            tracks.shuffle(&mut rand::thread_rng());
+        }
+        self.playlist = tracks.iter().map(|track| Song::from_track(track, client)).collect();
+        let _ = self.replace_playlist(); // TODO: inform user of error
+    }
+
+    fn reposition_track_cursor(&mut self, row: Option<(String, String)>) {
+        match row {
+            Some((album_id, song_id)) => self.select_track_row(&album_id, &song_id),
+            None => self.selected_track.select(if self.tracks.is_empty() { None } else { Some(0) }),
         }
     }
 
@@ -185,12 +275,17 @@ impl App {
             return;
         }
 
+        if self.popup.is_some() {
+            self.handle_popup_key_event(key_event).await;
+            return;
+        }
+
         if self.locally_searching {
             match key_event.code {
                 KeyCode::Esc | KeyCode::F(1) => {
                     self.locally_searching = false;
                     let artist_id = self.get_id_of_selected_artist();
-                    let track_id = self.get_id_of_selected_track();
+                    let track_row = self.selected_track_row().map(|row| self.track_row_key(row));
 
                     match self.active_section {
                         ActiveSection::Artists => {
@@ -199,7 +294,7 @@ impl App {
                         }
                         ActiveSection::Tracks => {
                             self.tracks_search_term = String::from("");
-                            self.reposition_track_cursor(&track_id);
+                            self.reposition_track_cursor(track_row);
                         }
                         _ => {}
                     }
@@ -224,9 +319,9 @@ impl App {
                             self.reposition_artist_cursor(&selected_id);
                         }
                         ActiveSection::Tracks => {
-                            let selected_id = self.get_id_of_selected_track();
+                            let selected_row = self.selected_track_row().map(|row| self.track_row_key(row));
                             self.tracks_search_term.pop();
-                            self.reposition_track_cursor(&selected_id);
+                            self.reposition_track_cursor(selected_row);
                         }
                         _ => {}
                     }
@@ -239,9 +334,9 @@ impl App {
                             self.reposition_artist_cursor(&selected_id);
                         }
                         ActiveSection::Tracks => {
-                            let selected_id = self.get_id_of_selected_track();
+                            let selected_row = self.selected_track_row().map(|row| self.track_row_key(row));
                             self.tracks_search_term.clear();
-                            self.reposition_track_cursor(&selected_id);
+                            self.reposition_track_cursor(selected_row);
                         }
                         _ => {}
                     }
@@ -277,6 +372,34 @@ impl App {
                     KeyCode::F(2) => {
                         self.searching = true;
                     }
+                    KeyCode::F(3) => {
+                        self.searching = false;
+                        self.open_playlists_tab().await;
+                    }
+                    KeyCode::F(4) => {
+                        self.searching = false;
+                        self.active_tab = ActiveTab::Downloads;
+                    }
+                    KeyCode::F(5) => {
+                        self.searching = false;
+                        self.open_sessions_tab();
+                    }
+                    KeyCode::F(6) => {
+                        self.searching = false;
+                        self.open_favorites_tab().await;
+                    }
+                    KeyCode::F(7) => {
+                        self.searching = false;
+                        self.open_dashboard_tab().await;
+                    }
+                    KeyCode::F(8) => {
+                        self.searching = false;
+                        self.open_genres_tab().await;
+                    }
+                    KeyCode::F(9) => {
+                        self.searching = false;
+                        self.open_albums_tab().await;
+                    }
                     KeyCode::Backspace => {
                         self.search_term.pop();
                     }
@@ -386,11 +509,8 @@ impl App {
                                         self.artists[selected].jellyfintui_recently_added = false;
                                         self.selected_track.select(Some(1));
 
-                                        // now find the first track that matches this album
-                                        if let Some(track) = self.tracks.iter().find(|t| t.album_id == album_id) {
-                                            let index = self.tracks.iter().position(|t| t.id == track.id).unwrap();
-                                            self.selected_track.select(Some(index));
-                                        }
+                                        // now find the row of this album
+                                        self.select_track_row(&album_id, "");
                                     }
                                 }
                                 SearchSection::Tracks => {
@@ -417,16 +537,13 @@ impl App {
                                         self.selected_artist.select(Some(index));
 
                                         let selected = self.selected_artist.selected().unwrap_or(0);
-                                        let track_id = track.id.clone();
+                                        let (album_id, track_id) = (track.album_id.clone(), track.id.clone());
                                         self.discography(&self.artists[selected].id.clone()).await;
                                         self.artists[selected].jellyfintui_recently_added = false;
                                         self.selected_track.select(Some(0));
 
-                                        // now find the first track that matches this album
-                                        if let Some(track) = self.tracks.iter().find(|t| t.id == track_id) {
-                                            let index = self.tracks.iter().position(|t| t.id == track.id).unwrap();
-                                            self.selected_track.select(Some(index));
-                                        }
+                                        // now find the row of this track
+                                        self.select_track_row(&album_id, &track_id);
                                     }
                                 }
                             }
@@ -541,12 +658,140 @@ impl App {
                             KeyCode::Char('/') => {
                                 self.searching = true;
                             }
+                            // Enqueue at the end / play next. Albums are queued whole
+                            KeyCode::Char('e') | KeyCode::Char('E') | KeyCode::Char('a') => {
+                                let next = key_event.code == KeyCode::Char('E');
+                                let tracks = match self.search_section {
+                                    SearchSection::Tracks => {
+                                        let track = match self.search_result_tracks.get(
+                                            self.selected_search_track.selected().unwrap_or(0)
+                                        ) {
+                                            Some(track) => track.clone(),
+                                            None => return,
+                                        };
+                                        if key_event.code == KeyCode::Char('a') {
+                                            match &self.client {
+                                                Some(client) => client.album_tracks(&track.album_id).await.unwrap_or_default(),
+                                                None => return,
+                                            }
+                                        } else {
+                                            vec![track]
+                                        }
+                                    }
+                                    SearchSection::Albums => {
+                                        let album_id = match self.search_result_albums.get(
+                                            self.selected_search_album.selected().unwrap_or(0)
+                                        ) {
+                                            Some(album) => album.id.clone(),
+                                            None => return,
+                                        };
+                                        match &self.client {
+                                            Some(client) => client.album_tracks(&album_id).await.unwrap_or_default(),
+                                            None => return,
+                                        }
+                                    }
+                                    SearchSection::Artists => return,
+                                };
+                                self.enqueue(tracks, next);
+                            }
+                            KeyCode::Char('f') => {
+                                if let Some((id, favorite)) = self.search_target() {
+                                    self.set_favorite(&id, !favorite).await;
+                                }
+                            }
+                            KeyCode::Char('i') => {
+                                if let Some((id, _)) = self.search_target() {
+                                    self.start_radio(&id).await;
+                                }
+                            }
+                            // Download for offline use
+                            KeyCode::Char('o') => {
+                                let client = match &self.client {
+                                    Some(client) => client,
+                                    None => return,
+                                };
+                                let tracks = match self.search_section {
+                                    SearchSection::Tracks => {
+                                        match self.search_result_tracks.get(
+                                            self.selected_search_track.selected().unwrap_or(0)
+                                        ) {
+                                            Some(track) => vec![track.clone()],
+                                            None => return,
+                                        }
+                                    }
+                                    SearchSection::Albums => {
+                                        match self.search_result_albums.get(
+                                            self.selected_search_album.selected().unwrap_or(0)
+                                        ) {
+                                            Some(album) => client.album_tracks(&album.id).await.unwrap_or_default(),
+                                            None => return,
+                                        }
+                                    }
+                                    SearchSection::Artists => {
+                                        match self.search_result_artists.get(
+                                            self.selected_search_artist.selected().unwrap_or(0)
+                                        ) {
+                                            Some(artist) => match client.discography(&artist.id).await {
+                                                Ok(discography) => discography.items,
+                                                Err(_) => return,
+                                            },
+                                            None => return,
+                                        }
+                                    }
+                                };
+                                self.download_tracks(tracks);
+                            }
+                            KeyCode::Char('p') => {
+                                if let SearchSection::Tracks = self.search_section {
+                                    if let Some(track) = self.search_result_tracks.get(
+                                        self.selected_search_track.selected().unwrap_or(0)
+                                    ) {
+                                        let track_ids = vec![track.id.clone()];
+                                        self.open_add_to_playlist_popup(track_ids).await;
+                                    }
+                                }
+                            }
                             _ => {}
                         }
                     }
                 }
                 return;
             }
+            ActiveTab::Playlists => {
+                if self.handle_playlists_key_event(key_event).await {
+                    return;
+                }
+            }
+            ActiveTab::Downloads => {
+                if self.handle_downloads_key_event(key_event) {
+                    return;
+                }
+            }
+            ActiveTab::Sessions => {
+                if self.handle_sessions_key_event(key_event).await {
+                    return;
+                }
+            }
+            ActiveTab::Favorites => {
+                if self.handle_favorites_key_event(key_event).await {
+                    return;
+                }
+            }
+            ActiveTab::Dashboard => {
+                if self.handle_dashboard_key_event(key_event).await {
+                    return;
+                }
+            }
+            ActiveTab::Genres => {
+                if self.handle_genres_key_event(key_event).await {
+                    return;
+                }
+            }
+            ActiveTab::Albums => {
+                if self.handle_albums_key_event(key_event).await {
+                    return;
+                }
+            }
             _ => {}
         }
 
@@ -554,15 +799,19 @@ impl App {
             KeyCode::Char('q') => self.exit(),
             // Seek backward
             KeyCode::Left | KeyCode::Char('r')  => {
-                if let Ok(mpv) = self.mpv_state.lock() {
-                    let _ = mpv.mpv.seek_backward(5.0);
+                let current_time = self.current_playback_state.duration * self.current_playback_state.percentage / 100.0;
+                if self.syncplay_seek(current_time - 5.0) {
+                    return;
                 }
+                self.player.send(PlayerCommand::Seek(current_time - 5.0));
             }
             // Seek forward
             KeyCode::Right | KeyCode::Char('s') => {
-                if let Ok(mpv) = self.mpv_state.lock() {
-                    let _ = mpv.mpv.seek_forward(5.0);
+                let current_time = self.current_playback_state.duration * self.current_playback_state.percentage / 100.0;
+                if self.syncplay_seek(current_time + 5.0) {
+                    return;
                 }
+                self.player.send(PlayerCommand::Seek(current_time + 5.0));
             }
             // Previous track
             KeyCode::Char('n') => {
@@ -572,43 +821,80 @@ impl App {
                         // position ticks
                         (self.current_playback_state.duration * self.current_playback_state.percentage * 100000.0) as u64,
                     ).await;
-                    if let Ok(mpv) = self.mpv_state.lock() {
-                        let _ = mpv.mpv.playlist_next_force();
+                    if self.syncplay_skip(true) {
+                        return;
                     }
+                    self.player.send(PlayerCommand::Next);
                 }
             }
             // Next track
             KeyCode::Char('N') => {
-                if let Ok(mpv) = self.mpv_state.lock() {
-                    let current_time = self.current_playback_state.duration * self.current_playback_state.percentage / 100.0;
-                    if current_time > 5.0 {
-                        let _ = mpv.mpv.seek_absolute(0.0);
-                        return;
-                    }
-                    let _ = mpv.mpv.playlist_previous_force();
+                let current_time = self.current_playback_state.duration * self.current_playback_state.percentage / 100.0;
+                if (current_time > 5.0 && self.syncplay_seek(0.0)) || self.syncplay_skip(false) {
+                    return;
                 }
+                if current_time > 5.0 {
+                    self.player.send(PlayerCommand::Seek(0.0));
+                    return;
+                }
+                self.player.send(PlayerCommand::Previous);
             }
             // Play/Pause
             KeyCode::Char(' ') => {
-                if let Ok(mpv) = self.mpv_state.lock() {
-                    if self.paused {
-                        let _ = mpv.mpv.unpause();
-                        self.paused = false;
-                    } else {
-                        let _ = mpv.mpv.pause();
-                        self.paused = true;
-                    }
+                if self.syncplay_pause(!self.paused) {
+                    return;
+                }
+                self.paused = !self.paused;
+                self.player.send(PlayerCommand::SetProperty("pause", self.paused.into()));
+            }
+            // Favorite / unfavorite
+            KeyCode::Char('f') => {
+                if let Some((id, favorite)) = self.target_item() {
+                    self.set_favorite(&id, !favorite).await;
                 }
             }
+            // Instant mix of the selection, or of the playing song
+            KeyCode::Char('i') => {
+                if let Some((id, _)) = self.target_item() {
+                    self.start_radio(&id).await;
+                }
+            }
+            // Auto-DJ, keep the queue going with mixes of its last song
+            KeyCode::Char('I') => {
+                self.auto_dj = !self.auto_dj;
+                self.top_up_auto_dj();
+            }
+            // Create, join or leave a SyncPlay group
+            KeyCode::Char('y') => {
+                self.open_syncplay_popup().await;
+            }
+            // Direct play / transcode, for songs queued from now on
+            KeyCode::Char('T') => {
+                if let Some(client) = self.client.as_mut() {
+                    client.transcoding.enabled = !client.transcoding.enabled;
+                }
+            }
+            // Loudness normalization off / track / album, for songs queued from now on
+            KeyCode::Char('L') => {
+                if let Some(client) = self.client.as_mut() {
+                    client.normalization.mode = client.normalization.mode.next();
+                }
+            }
+            // Shuffle the rest of the queue
+            KeyCode::Char('z') => {
+                let _ = self.toggle_shuffle(); // TODO: inform user of error
+            }
+            // Repeat off / all / one
+            KeyCode::Char('x') => {
+                let _ = self.cycle_repeat(); // TODO: inform user of error
+            }
             // Volume up
             KeyCode::Char('+') => {
-                if self.current_playback_state.volume >= 500 {
+                if self.current_playback_state.volume >= MAX_VOLUME {
                     return;
                 }
                 self.current_playback_state.volume += 5;
-                if let Ok(mpv) = self.mpv_state.lock() {
-                    let _ = mpv.mpv.set_property("volume", self.current_playback_state.volume);
-                }
+                self.player.send(PlayerCommand::SetProperty("volume", self.current_playback_state.volume.into()));
             }
             // Volume down
             KeyCode::Char('-') => {
@@ -616,12 +902,7 @@ impl App {
                     return;
                 }
                 self.current_playback_state.volume -= 5;
-                match self.mpv_state.lock() {
-                    Ok(mpv) => {
-                        let _ = mpv.mpv.set_property("volume", self.current_playback_state.volume);
-                    }
-                    Err(_) => {}
-                }
+                self.player.send(PlayerCommand::SetProperty("volume", self.current_playback_state.volume.into()));
             }
             KeyCode::Tab => {
                 self.toggle_section(true);
@@ -664,38 +945,17 @@ impl App {
                     self.selected_artist.select(Some(selected + 1));
                 }
                 ActiveSection::Tracks => {
-
-                   if self.tracks_search_term.len() > 0 {
-                        let items = self.track_search_results();
-                        let selected = self
-                            .selected_track
-                            .selected()
-                            .unwrap_or(items.len() - 1);
-                        if selected == items.len() - 1 {
-                            self.selected_track.select(Some(selected));
-                            return;
-                        }
+                    let len = self.track_rows().len();
+                    let selected = self.selected_track.selected().unwrap_or(0);
+                    if selected + 1 < len {
                         self.selected_track.select(Some(selected + 1));
-                        return;
-                    }
-
-                    let selected = self
-                        .selected_track
-                        .selected()
-                        .unwrap_or(self.tracks.len() - 1);
-                    if selected == self.tracks.len() - 1 {
-                        self.selected_track.select(Some(selected));
-                        return;
-                    }
-                    self.selected_track.select(Some(selected + 1));
-                    if self.tracks.len() > 0 {
-                        if self.tracks[self.selected_track.selected().unwrap()].id == "_album_" {
-                            self.selected_track.select(Some(selected + 2));
-                        }
                     }
                 }
                 ActiveSection::Queue => {
-                    *self.selected_queue_item.offset_mut() += 1;
+                    let selected = self.selected_queue_item.selected().unwrap_or(0);
+                    if selected + 1 < self.playlist.len() {
+                        self.selected_queue_item.select(Some(selected + 1));
+                    }
                 }
                 ActiveSection::Lyrics => {
                     self.selected_lyric_manual_override = true;
@@ -738,42 +998,16 @@ impl App {
                     self.selected_artist.select(Some(selected - 1));
                 }
                 ActiveSection::Tracks => {
-
-                    if self.tracks_search_term.len() > 0 {
-                        let selected = self
-                            .selected_track
-                            .selected()
-                            .unwrap_or(0);
-                        if selected == 0 {
-                            self.selected_track.select(Some(selected));
-                            return;
-                        }
-                        self.selected_track.select(Some(selected - 1));
-                        return;
-                    }
-
                     let selected = self.selected_track.selected().unwrap_or(0);
-                    if selected == 0 {
-                        self.selected_track.select(Some(selected));
-                        return;
-                    }
-                    self.selected_track.select(Some(selected - 1));
-                    if self.tracks.len() > 0 {
-                        if self.tracks[self.selected_track.selected().unwrap()].id == "_album_" {
-                            if selected == 1 {
-                                self.selected_track.select(Some(1));
-                            } else {
-                                self.selected_track.select(Some(selected - 2));
-                            }
-                        }
+                    if selected > 0 {
+                        self.selected_track.select(Some(selected - 1));
                     }
                 }
                 ActiveSection::Queue => {
-                    let lvalue = self.selected_queue_item.offset_mut();
-                    if *lvalue == 0 {
-                        return;
+                    let selected = self.selected_queue_item.selected().unwrap_or(0);
+                    if selected > 0 {
+                        self.selected_queue_item.select(Some(selected - 1));
                     }
-                    *lvalue -= 1;
                 }
                 ActiveSection::Lyrics => {
                     self.selected_lyric_manual_override = true;
@@ -790,11 +1024,7 @@ impl App {
                     self.selected_artist.select(Some(0));
                 }
                 ActiveSection::Tracks => {
-                    if self.tracks_search_term.len() > 0 {
-                        self.selected_track.select(Some(0));
-                        return;
-                    }
-                    self.selected_track.select(Some(1));
+                    self.selected_track.select(Some(0));
                 }
                 ActiveSection::Queue => {
                     self.selected_queue_item.select(Some(0));
@@ -811,8 +1041,9 @@ impl App {
                     }
                 }
                 ActiveSection::Tracks => {
-                    if self.tracks.len() != 0 {
-                        self.selected_track.select(Some(self.tracks.len() - 1));
+                    let len = self.track_rows().len();
+                    if len != 0 {
+                        self.selected_track.select(Some(len - 1));
                     }
                 }
                 ActiveSection::Queue => {
@@ -869,42 +1100,26 @@ impl App {
                     }
                     ActiveSection::Tracks => {
                         let selected = self.selected_track.selected().unwrap_or(0);
-                        if let Some(client) = &self.client {
-                            if let Ok(mut mpv) = self.mpv_state.lock() {
-                                let _ = mpv.should_stop = true;
-                            }
-
-                            // the playlist MPV will be getting
-                            self.playlist = self
-                                .tracks
-                                .iter()
+                        let tracks = match self.selected_track_row() {
+                            // an album row plays only that album
+                            Some(TrackRow::Album(first)) => self.album_songs(first).to_vec(),
+                            // a song plays from there on, through the rows that are shown
+                            Some(TrackRow::Song(_)) => self
+                                .track_rows()
+                                .into_iter()
                                 .skip(selected)
-                                .filter(|track| track.id != "_album_")
-                                .map(|track| {
-                                    Song {
-                                        id: track.id.clone(),
-                                        url: client.song_url_sync(track.id.clone()),
-                                        name: track.name.clone(),
-                                        artist: track.album_artist.clone(),
-                                        artist_items: track.artist_items.clone(),
-                                        album: track.album.clone(),
-                                        parent_id: track.parent_id.clone(),
-                                        production_year: track.production_year,
-                                    }
+                                .filter_map(|row| match row {
+                                    TrackRow::Song(i) => Some(self.tracks[i].clone()),
+                                    TrackRow::Album(_) => None,
                                 })
-                                .collect();
-
-                            if self.tracks_search_term.len() > 0 {
-                                self.playlist = self.playlist.iter().filter(|track| {
-                                    track.name.to_lowercase().contains(&self.tracks_search_term.to_lowercase())
-                                }).map(|track| track.clone()).collect();
-                            }
-                            let _ = self.replace_playlist(); // TODO: inform user of error
-                        }
+                                .collect(),
+                            None => return,
+                        };
+                        self.play_tracks(tracks, false);
                     }
                     ActiveSection::Queue => {
-                        let _ = self.selected_queue_item.selected().unwrap_or(0);
-                        // println!("Selected queue item: {:?}", selected);
+                        let selected = self.selected_queue_item.selected().unwrap_or(0);
+                        let _ = self.playlist_jump(selected); // TODO: inform user of error
                     }
                     ActiveSection::Lyrics => {
                         // jump to that timestamp
@@ -915,12 +1130,10 @@ impl App {
                                 let time = lyric.start as f64 / 10_000_000.0;
                                 
                                 if time != 0.0 {
-                                    if let Ok(mpv) = self.mpv_state.lock() {
-                                        let _ = mpv.mpv.seek_absolute(time);
-                                        let _ = mpv.mpv.unpause();
-                                        self.paused = false;
-                                        self.buffering = 1;
-                                    }
+                                    self.player.send(PlayerCommand::Seek(time));
+                                    self.player.send(PlayerCommand::SetProperty("pause", false.into()));
+                                    self.paused = false;
+                                    self.buffering = 1;
                                 }
                             }
                         }
@@ -930,7 +1143,7 @@ impl App {
             KeyCode::Esc | KeyCode::F(1) => {
                 self.active_tab = ActiveTab::Library;
                 let artist_id = self.get_id_of_selected_artist();
-                let track_id = self.get_id_of_selected_track();
+                let track_row = self.selected_track_row().map(|row| self.track_row_key(row));
 
                 match self.active_section {
                     ActiveSection::Artists => {
@@ -939,7 +1152,7 @@ impl App {
                     }
                     ActiveSection::Tracks => {
                         self.tracks_search_term = String::from("");
-                        self.reposition_track_cursor(&track_id);
+                        self.reposition_track_cursor(track_row);
                     }
                     _ => {}
                 }
@@ -948,13 +1161,996 @@ impl App {
                 self.active_tab = ActiveTab::Search;
                 self.searching = true;
             }
+            KeyCode::F(3) => {
+                self.open_playlists_tab().await;
+            }
+            KeyCode::F(4) => {
+                self.active_tab = ActiveTab::Downloads;
+            }
+            KeyCode::F(5) => {
+                self.open_sessions_tab();
+            }
+            KeyCode::F(6) => {
+                self.open_favorites_tab().await;
+            }
+            KeyCode::F(7) => {
+                self.open_dashboard_tab().await;
+            }
+            KeyCode::F(8) => {
+                self.open_genres_tab().await;
+            }
+            KeyCode::F(9) => {
+                self.open_albums_tab().await;
+            }
             KeyCode::Char('/') => {
                 self.locally_searching = true;
             }
+            // Remove from the queue
+            KeyCode::Char('d') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Queue {
+                    let selected = self.selected_queue_item.selected().unwrap_or(0);
+                    let _ = self.playlist_remove(selected);
+                    if selected >= self.playlist.len() {
+                        self.selected_queue_item.select(self.playlist.len().checked_sub(1));
+                    }
+                }
+            }
+            // Move down / up in the queue
+            KeyCode::Char('J') | KeyCode::Char('K') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Queue {
+                    let selected = self.selected_queue_item.selected().unwrap_or(0);
+                    let to = if key_event.code == KeyCode::Char('J') {
+                        selected + 1
+                    } else if selected > 0 {
+                        selected - 1
+                    } else {
+                        return;
+                    };
+                    if to < self.playlist.len() && self.playlist_move(selected, to).is_ok() {
+                        self.selected_queue_item.select(Some(to));
+                    }
+                }
+            }
+            // Clear everything after the current song
+            KeyCode::Char('X') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Queue {
+                    let _ = self.playlist_clear_after_current();
+                    let selected = self.selected_queue_item.selected().unwrap_or(0);
+                    if selected >= self.playlist.len() {
+                        self.selected_queue_item.select(self.playlist.len().checked_sub(1));
+                    }
+                }
+            }
+            // Enqueue at the end / play next
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Tracks {
+                    let tracks = self.selected_tracks();
+                    self.enqueue(tracks, key_event.code == KeyCode::Char('E'));
+                }
+            }
+            // Enqueue the whole album
+            KeyCode::Char('a') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Tracks {
+                    let tracks = self.selected_album_tracks();
+                    self.enqueue(tracks, false);
+                }
+            }
+            // Shuffle the whole album
+            KeyCode::Char('S') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Tracks {
+                    let tracks = self.selected_album_tracks();
+                    self.play_tracks(tracks, true);
+                }
+            }
+            // Fold / unfold the album
+            KeyCode::Char('c') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Tracks {
+                    self.toggle_album_collapsed();
+                }
+            }
+            // Download for offline use
+            KeyCode::Char('o') => {
+                if self.active_tab != ActiveTab::Library {
+                    return;
+                }
+                // there is nothing to download from while offline
+                let client = match &self.client {
+                    Some(client) if !client.offline => client,
+                    _ => return,
+                };
+                let tracks = match self.active_section {
+                    ActiveSection::Tracks => self.selected_tracks(),
+                    ActiveSection::Artists => {
+                        let artist_id = self.get_id_of_selected_artist();
+                        match client.discography(&artist_id).await {
+                            Ok(discography) => discography.items,
+                            Err(_) => return,
+                        }
+                    }
+                    _ => return,
+                };
+                self.download_tracks(tracks);
+            }
+            // Add to playlist
+            KeyCode::Char('p') => {
+                if self.active_tab != ActiveTab::Library {
+                    return;
+                }
+                let track_ids = match self.active_section {
+                    ActiveSection::Tracks => self.selected_tracks().into_iter().map(|track| track.id).collect(),
+                    ActiveSection::Queue => {
+                        match self.playlist.get(self.selected_queue_item.selected().unwrap_or(0)) {
+                            Some(song) => vec![song.id.clone()],
+                            None => vec![],
+                        }
+                    }
+                    _ => vec![],
+                };
+                if !track_ids.is_empty() {
+                    self.open_add_to_playlist_popup(track_ids).await;
+                }
+            }
+            KeyCode::Char('C') => {
+                if self.active_tab == ActiveTab::Library && self.active_section == ActiveSection::Tracks {
+                    self.popup = Some(Popup::new(PopupAction::TrackColumns));
+                }
+            }
             _ => {}
         }
     }
 
+    /// Keys of the Playlists tab. Returns false for keys that should fall through to the global player keys
+    async fn handle_playlists_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.playlists_section = match self.playlists_section {
+                    PlaylistsSection::Playlists => PlaylistsSection::Tracks,
+                    PlaylistsSection::Tracks => PlaylistsSection::Playlists,
+                };
+            }
+            KeyCode::Char('h') => {
+                self.playlists_section = PlaylistsSection::Playlists;
+            }
+            KeyCode::Char('l') => {
+                self.playlists_section = PlaylistsSection::Tracks;
+            }
+            KeyCode::Down | KeyCode::Char('j') => match self.playlists_section {
+                PlaylistsSection::Playlists => {
+                    let selected = self.selected_playlist.selected().unwrap_or(0);
+                    if selected + 1 < self.playlists.len() {
+                        self.selected_playlist.select(Some(selected + 1));
+                    }
+                }
+                PlaylistsSection::Tracks => {
+                    let selected = self.selected_playlist_track.selected().unwrap_or(0);
+                    if selected + 1 < self.playlist_tracks.len() {
+                        self.selected_playlist_track.select(Some(selected + 1));
+                    }
+                }
+            },
+            KeyCode::Up | KeyCode::Char('k') => match self.playlists_section {
+                PlaylistsSection::Playlists => {
+                    let selected = self.selected_playlist.selected().unwrap_or(0);
+                    if selected > 0 {
+                        self.selected_playlist.select(Some(selected - 1));
+                    }
+                }
+                PlaylistsSection::Tracks => {
+                    let selected = self.selected_playlist_track.selected().unwrap_or(0);
+                    if selected > 0 {
+                        self.selected_playlist_track.select(Some(selected - 1));
+                    }
+                }
+            },
+            KeyCode::Char('g') => match self.playlists_section {
+                PlaylistsSection::Playlists => {
+                    self.selected_playlist.select(Some(0));
+                }
+                PlaylistsSection::Tracks => {
+                    self.selected_playlist_track.select(Some(0));
+                }
+            },
+            KeyCode::Char('G') => match self.playlists_section {
+                PlaylistsSection::Playlists => {
+                    if !self.playlists.is_empty() {
+                        self.selected_playlist.select(Some(self.playlists.len() - 1));
+                    }
+                }
+                PlaylistsSection::Tracks => {
+                    if !self.playlist_tracks.is_empty() {
+                        self.selected_playlist_track.select(Some(self.playlist_tracks.len() - 1));
+                    }
+                }
+            },
+            KeyCode::Enter => match self.playlists_section {
+                PlaylistsSection::Playlists => {
+                    if let Some(playlist) = self.playlists.get(self.selected_playlist.selected().unwrap_or(0)) {
+                        let id = playlist.id.clone();
+                        self.open_playlist(&id).await;
+                    }
+                }
+                PlaylistsSection::Tracks => {
+                    let selected = self.selected_playlist_track.selected().unwrap_or(0);
+                    if let Some(client) = &self.client {
+                        self.playlist = self
+                            .playlist_tracks
+                            .iter()
+                            .skip(selected)
+                            .map(|track| Song::from_track(track, client))
+                            .collect();
+                        let _ = self.replace_playlist(); // TODO: inform user of error
+                    }
+                }
+            },
+            // New playlist
+            KeyCode::Char('c') => {
+                self.popup = Some(Popup::new(PopupAction::CreatePlaylist));
+            }
+            // Rename playlist
+            KeyCode::Char('R') => {
+                if self.playlists_section != PlaylistsSection::Playlists {
+                    return true;
+                }
+                if let Some(playlist) = self.playlists.get(self.selected_playlist.selected().unwrap_or(0)) {
+                    let mut popup = Popup::new(PopupAction::RenamePlaylist);
+                    popup.input = playlist.name.clone();
+                    popup.playlist_id = playlist.id.clone();
+                    self.popup = Some(popup);
+                }
+            }
+            // Remove from playlist
+            KeyCode::Char('d') => {
+                if self.playlists_section != PlaylistsSection::Tracks {
+                    return true;
+                }
+                let selected = self.selected_playlist_track.selected().unwrap_or(0);
+                let entry_id = match self.playlist_tracks.get(selected) {
+                    Some(track) => track.playlist_item_id.clone(),
+                    None => return true,
+                };
+                if let Some(client) = &self.client {
+                    if client.remove_from_playlist(&self.open_playlist_id, &[entry_id]).await.is_ok() {
+                        self.playlist_tracks.remove(selected);
+                        if let Some(playlist) = self.playlists.iter_mut().find(|p| p.id == self.open_playlist_id) {
+                            playlist.child_count = playlist.child_count.saturating_sub(1);
+                        }
+                        if selected >= self.playlist_tracks.len() {
+                            self.selected_playlist_track.select(self.playlist_tracks.len().checked_sub(1));
+                        }
+                    }
+                }
+            }
+            // Move entry down / up
+            KeyCode::Char('J') | KeyCode::Char('K') => {
+                if self.playlists_section != PlaylistsSection::Tracks {
+                    return true;
+                }
+                let selected = self.selected_playlist_track.selected().unwrap_or(0);
+                let new_index = if key_event.code == KeyCode::Char('J') {
+                    selected + 1
+                } else if selected > 0 {
+                    selected - 1
+                } else {
+                    return true;
+                };
+                if new_index >= self.playlist_tracks.len() {
+                    return true;
+                }
+                let entry_id = self.playlist_tracks[selected].playlist_item_id.clone();
+                if let Some(client) = &self.client {
+                    if client.move_playlist_item(&self.open_playlist_id, &entry_id, new_index).await.is_ok() {
+                        self.playlist_tracks.swap(selected, new_index);
+                        self.selected_playlist_track.select(Some(new_index));
+                    }
+                }
+            }
+            // Add to another playlist
+            KeyCode::Char('p') => {
+                if self.playlists_section != PlaylistsSection::Tracks {
+                    return true;
+                }
+                if let Some(track) = self.playlist_tracks.get(self.selected_playlist_track.selected().unwrap_or(0)) {
+                    let track_ids = vec![track.id.clone()];
+                    self.open_add_to_playlist_popup(track_ids).await;
+                }
+            }
+            // Download for offline use
+            KeyCode::Char('o') => {
+                if self.playlists_section != PlaylistsSection::Tracks {
+                    return true;
+                }
+                if let Some(track) = self.playlist_tracks.get(self.selected_playlist_track.selected().unwrap_or(0)) {
+                    let tracks = vec![track.clone()];
+                    self.download_tracks(tracks);
+                }
+            }
+            // Favorite / unfavorite the selected track, or the playing song from the playlist list
+            KeyCode::Char('f') => {
+                if self.playlists_section != PlaylistsSection::Tracks {
+                    return false;
+                }
+                let target = self
+                    .playlist_tracks
+                    .get(self.selected_playlist_track.selected().unwrap_or(0))
+                    .map(|track| (track.id.clone(), track.user_data.is_favorite));
+                if let Some((id, favorite)) = target {
+                    self.set_favorite(&id, !favorite).await;
+                }
+            }
+            KeyCode::F(3) => {
+                self.refresh_playlists().await;
+            }
+            // keep the hidden Library tab as it was left
+            code if is_library_navigation(code) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of the Downloads tab. Returns false for keys that should fall through to the global player keys
+    fn handle_downloads_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                let selected = self.selected_download.selected().unwrap_or(0);
+                if selected + 1 < self.downloads.len() {
+                    self.selected_download.select(Some(selected + 1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let selected = self.selected_download.selected().unwrap_or(0);
+                if selected > 0 {
+                    self.selected_download.select(Some(selected - 1));
+                }
+            }
+            KeyCode::Char('g') => {
+                if !self.downloads.is_empty() {
+                    self.selected_download.select(Some(0));
+                }
+            }
+            KeyCode::Char('G') => {
+                if !self.downloads.is_empty() {
+                    self.selected_download.select(Some(self.downloads.len() - 1));
+                }
+            }
+            // Play the finished downloads, starting at the selected one
+            KeyCode::Enter => {
+                let selected = self.selected_download.selected().unwrap_or(0);
+                let client = match &self.client {
+                    Some(client) => client,
+                    None => return true,
+                };
+                let songs = self
+                    .downloads
+                    .iter()
+                    .skip(selected)
+                    .filter(|download| download.status == DownloadStatus::Done)
+                    .map(|download| Song {
+                        url: client.song_url_sync(download.song.id.clone()),
+                        ..download.song.clone()
+                    })
+                    .collect::<Vec<Song>>();
+                if songs.is_empty() {
+                    return true;
+                }
+                self.playlist = songs;
+                let _ = self.replace_playlist(); // TODO: inform user of error
+            }
+            // Delete from the cache
+            KeyCode::Char('d') => {
+                let selected = self.selected_download.selected().unwrap_or(0);
+                self.delete_download(selected);
+            }
+            // keep the hidden Library tab as it was left
+            code if is_library_navigation(code) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of the Sessions tab. The player keys act on the selected session instead of the local player,
+    /// the rest fall through
+    async fn handle_sessions_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                let selected = self.selected_session.selected().unwrap_or(0);
+                if selected + 1 < self.sessions.len() {
+                    self.selected_session.select(Some(selected + 1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let selected = self.selected_session.selected().unwrap_or(0);
+                if selected > 0 {
+                    self.selected_session.select(Some(selected - 1));
+                }
+            }
+            KeyCode::Char('g') => {
+                if !self.sessions.is_empty() {
+                    self.selected_session.select(Some(0));
+                }
+            }
+            KeyCode::Char('G') => {
+                if !self.sessions.is_empty() {
+                    self.selected_session.select(Some(self.sessions.len() - 1));
+                }
+            }
+            KeyCode::Enter => {
+                self.transfer_queue().await;
+            }
+            KeyCode::Char(' ') | KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('S') => {
+                let command = match key_event.code {
+                    KeyCode::Char(' ') => "PlayPause",
+                    KeyCode::Char('n') => "NextTrack",
+                    KeyCode::Char('N') => "PreviousTrack",
+                    _ => "Stop",
+                };
+                if let (Some(client), Some(session)) = (self.client.as_ref(), self.selected_session()) {
+                    let _ = client.session_playstate(&session.id, command, None).await; // TODO: inform user of error
+                }
+                self.refresh_sessions(true);
+            }
+            KeyCode::Left | KeyCode::Char('r') | KeyCode::Right | KeyCode::Char('s') => {
+                if let (Some(client), Some(session)) = (self.client.as_ref(), self.selected_session()) {
+                    // 5 seconds, in ticks
+                    let position = session.play_state.position_ticks;
+                    let ticks = match key_event.code {
+                        KeyCode::Left | KeyCode::Char('r') => position.saturating_sub(50_000_000),
+                        _ => position + 50_000_000,
+                    };
+                    let _ = client.session_playstate(&session.id, "Seek", Some(ticks)).await; // TODO: inform user of error
+                }
+                self.refresh_sessions(true);
+            }
+            KeyCode::Char('+') | KeyCode::Char('-') => {
+                let command = if key_event.code == KeyCode::Char('+') { "VolumeUp" } else { "VolumeDown" };
+                if let (Some(client), Some(session)) = (self.client.as_ref(), self.selected_session()) {
+                    let _ = client.session_command(&session.id, command).await; // TODO: inform user of error
+                }
+            }
+            KeyCode::F(5) => {
+                self.refresh_sessions(true);
+            }
+            // keep the hidden Library tab as it was left
+            code if is_library_navigation(code) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of the Favorites tab. Returns false for keys that should fall through to the global player keys
+    async fn handle_favorites_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                let selected = self.selected_favorite.selected().unwrap_or(0);
+                if selected + 1 < self.favorite_tracks.len() {
+                    self.selected_favorite.select(Some(selected + 1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let selected = self.selected_favorite.selected().unwrap_or(0);
+                if selected > 0 {
+                    self.selected_favorite.select(Some(selected - 1));
+                }
+            }
+            KeyCode::Char('g') => {
+                if !self.favorite_tracks.is_empty() {
+                    self.selected_favorite.select(Some(0));
+                }
+            }
+            KeyCode::Char('G') => {
+                if !self.favorite_tracks.is_empty() {
+                    self.selected_favorite.select(Some(self.favorite_tracks.len() - 1));
+                }
+            }
+            // Play from the selected song, or all of them
+            KeyCode::Enter | KeyCode::Char('a') => {
+                let skip = if key_event.code == KeyCode::Enter {
+                    self.selected_favorite.selected().unwrap_or(0)
+                } else {
+                    0
+                };
+                let client = match &self.client {
+                    Some(client) => client,
+                    None => return true,
+                };
+                let songs = self
+                    .favorite_tracks
+                    .iter()
+                    .skip(skip)
+                    .filter(|track| track.user_data.is_favorite)
+                    .map(|track| Song::from_track(track, client))
+                    .collect::<Vec<Song>>();
+                if songs.is_empty() {
+                    return true;
+                }
+                self.playlist = songs;
+                let _ = self.replace_playlist(); // TODO: inform user of error
+            }
+            KeyCode::Char('f') => {
+                let target = self
+                    .favorite_tracks
+                    .get(self.selected_favorite.selected().unwrap_or(0))
+                    .map(|track| (track.id.clone(), track.user_data.is_favorite));
+                if let Some((id, favorite)) = target {
+                    self.set_favorite(&id, !favorite).await;
+                }
+            }
+            KeyCode::F(6) => {
+                self.refresh_favorites().await;
+            }
+            // keep the hidden Library tab as it was left
+            code if is_library_navigation(code) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of the Dashboard tab. Returns false for keys that should fall through to the global player keys
+    async fn handle_dashboard_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Tab => {
+                self.dashboard_section = self.dashboard_section.next();
+            }
+            KeyCode::BackTab => {
+                self.dashboard_section = self.dashboard_section.previous();
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let (state, len) = self.dashboard_list();
+                let selected = state.selected().unwrap_or(0);
+                if selected + 1 < len {
+                    state.select(Some(selected + 1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let (state, _) = self.dashboard_list();
+                let selected = state.selected().unwrap_or(0);
+                if selected > 0 {
+                    state.select(Some(selected - 1));
+                }
+            }
+            KeyCode::Char('g') => {
+                let (state, len) = self.dashboard_list();
+                if len > 0 {
+                    state.select(Some(0));
+                }
+            }
+            KeyCode::Char('G') => {
+                let (state, len) = self.dashboard_list();
+                if len > 0 {
+                    state.select(Some(len - 1));
+                }
+            }
+            // Play the selected album, or the songs from the selected one
+            KeyCode::Enter => {
+                let tracks = self.dashboard_selection(true).await;
+                let client = match &self.client {
+                    Some(client) => client,
+                    None => return true,
+                };
+                let songs = tracks.iter().map(|track| Song::from_track(track, client)).collect::<Vec<Song>>();
+                if songs.is_empty() {
+                    return true;
+                }
+                self.playlist = songs;
+                let _ = self.replace_playlist(); // TODO: inform user of error
+            }
+            // Enqueue the selected album or song at the end, or play it next
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                let tracks = self.dashboard_selection(false).await;
+                let client = match &self.client {
+                    Some(client) => client,
+                    None => return true,
+                };
+                let songs = tracks.iter().map(|track| Song::from_track(track, client)).collect::<Vec<Song>>();
+                if songs.is_empty() {
+                    return true;
+                }
+                let _ = if key_event.code == KeyCode::Char('e') {
+                    self.append_to_playlist(songs)
+                } else {
+                    self.push_next_to_playlist(songs)
+                }; // TODO: inform user of error
+            }
+            KeyCode::F(7) => {
+                self.refresh_dashboard().await;
+            }
+            // keep the hidden Library tab as it was left
+            code if is_library_navigation(code) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of the Genres tab. Returns false for keys that should fall through to the global player keys
+    async fn handle_genres_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Tab => {
+                self.genres_section = match self.genres_section {
+                    GenresSection::Genres => GenresSection::Artists,
+                    GenresSection::Artists => GenresSection::Albums,
+                    GenresSection::Albums => GenresSection::Genres,
+                };
+            }
+            KeyCode::BackTab => {
+                self.genres_section = match self.genres_section {
+                    GenresSection::Genres => GenresSection::Albums,
+                    GenresSection::Artists => GenresSection::Genres,
+                    GenresSection::Albums => GenresSection::Artists,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let (state, len) = self.genres_column();
+                let selected = state.selected().unwrap_or(0);
+                if selected + 1 < len {
+                    state.select(Some(selected + 1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let (state, _) = self.genres_column();
+                let selected = state.selected().unwrap_or(0);
+                if selected > 0 {
+                    state.select(Some(selected - 1));
+                }
+            }
+            KeyCode::Char('g') => {
+                let (state, len) = self.genres_column();
+                if len > 0 {
+                    state.select(Some(0));
+                }
+            }
+            KeyCode::Char('G') => {
+                let (state, len) = self.genres_column();
+                if len > 0 {
+                    state.select(Some(len - 1));
+                }
+            }
+            // Drill into the genre, or show the artist or album in the library
+            KeyCode::Enter => match self.genres_section {
+                GenresSection::Genres => {
+                    self.open_genre().await;
+                    self.genres_section = GenresSection::Artists;
+                }
+                GenresSection::Artists => {
+                    let artist_id = match self.genre_artists.get(self.selected_genre_artist.selected().unwrap_or(0)) {
+                        Some(artist) => artist.id.clone(),
+                        None => return true,
+                    };
+                    self.open_in_library(&artist_id, "").await;
+                }
+                GenresSection::Albums => {
+                    let (artist_id, album_id) = match self.genre_albums.get(self.selected_genre_album.selected().unwrap_or(0)) {
+                        Some(album) => (
+                            album.album_artists.first().map(|artist| artist.id.clone()).unwrap_or_default(),
+                            album.id.clone(),
+                        ),
+                        None => return true,
+                    };
+                    self.open_in_library(&artist_id, &album_id).await;
+                }
+            },
+            KeyCode::Char('S') => {
+                self.shuffle_genre().await;
+            }
+            KeyCode::F(8) => {
+                self.refresh_genres().await;
+            }
+            // keep the hidden Library tab as it was left
+            code if is_library_navigation(code) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Switch to the Genres tab, fetching the genres the first time
+    async fn open_genres_tab(&mut self) {
+        self.active_tab = ActiveTab::Genres;
+        if self.genres.is_empty() {
+            self.refresh_genres().await;
+        }
+    }
+
+    /// Keys of the Albums tab. Returns false for keys that should fall through to the global player keys
+    async fn handle_albums_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.albums_section = match self.albums_section {
+                    AlbumsSection::Albums => AlbumsSection::Tracks,
+                    AlbumsSection::Tracks => AlbumsSection::Albums,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let (state, len) = match self.albums_section {
+                    AlbumsSection::Albums => (&mut self.selected_album, self.albums.len()),
+                    AlbumsSection::Tracks => (&mut self.selected_album_track, self.album_tracks.len()),
+                };
+                let selected = state.selected().unwrap_or(0);
+                if selected + 1 < len {
+                    state.select(Some(selected + 1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let state = match self.albums_section {
+                    AlbumsSection::Albums => &mut self.selected_album,
+                    AlbumsSection::Tracks => &mut self.selected_album_track,
+                };
+                let selected = state.selected().unwrap_or(0);
+                if selected > 0 {
+                    state.select(Some(selected - 1));
+                }
+            }
+            KeyCode::Char('g') => {
+                let (state, len) = match self.albums_section {
+                    AlbumsSection::Albums => (&mut self.selected_album, self.albums.len()),
+                    AlbumsSection::Tracks => (&mut self.selected_album_track, self.album_tracks.len()),
+                };
+                if len > 0 {
+                    state.select(Some(0));
+                }
+            }
+            KeyCode::Char('G') => {
+                let (state, len) = match self.albums_section {
+                    AlbumsSection::Albums => (&mut self.selected_album, self.albums.len()),
+                    AlbumsSection::Tracks => (&mut self.selected_album_track, self.album_tracks.len()),
+                };
+                if len > 0 {
+                    state.select(Some(len - 1));
+                }
+            }
+            // Open the album, or play it from the selected song
+            KeyCode::Enter => match self.albums_section {
+                AlbumsSection::Albums => {
+                    self.open_album().await;
+                    if !self.album_tracks.is_empty() {
+                        self.albums_section = AlbumsSection::Tracks;
+                    }
+                }
+                AlbumsSection::Tracks => {
+                    let skip = self.selected_album_track.selected().unwrap_or(0);
+                    self.play_album(skip, false).await;
+                }
+            },
+            // The whole album, in order or shuffled
+            KeyCode::Char('a') | KeyCode::Char('S') => {
+                if self.albums_section == AlbumsSection::Albums {
+                    self.open_selected_album().await;
+                }
+                self.play_album(0, key_event.code == KeyCode::Char('S')).await;
+            }
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                if self.albums_section == AlbumsSection::Albums {
+                    self.open_selected_album().await;
+                }
+                let tracks = self.album_tracks.clone();
+                self.enqueue(tracks, key_event.code == KeyCode::Char('E'));
+            }
+            KeyCode::Char('O') => {
+                self.album_sort = self.album_sort.next();
+                self.sort_albums();
+            }
+            KeyCode::F(9) => {
+                self.refresh_albums().await;
+            }
+            // keep the hidden Library tab as it was left
+            code if is_library_navigation(code) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Switch to the Albums tab, fetching the albums the first time
+    async fn open_albums_tab(&mut self) {
+        self.active_tab = ActiveTab::Albums;
+        if self.albums.is_empty() {
+            self.refresh_albums().await;
+        }
+    }
+
+    /// Show an artist in the Library tab, with the cursor on the row of `album_id` if given
+    async fn open_in_library(&mut self, artist_id: &str, album_id: &str) {
+        self.active_tab = ActiveTab::Library;
+        self.active_section = ActiveSection::Artists;
+        let index = match self.artists.iter().position(|artist| artist.id == artist_id) {
+            Some(index) => index,
+            None => return,
+        };
+        self.selected_artist.select(Some(index));
+        self.discography(artist_id).await;
+        self.artists[index].jellyfintui_recently_added = false;
+        self.selected_track.select(Some(1));
+        self.select_track_row(album_id, "");
+    }
+
+    /// Switch to the Dashboard tab, fetching it every time so recent plays show up
+    async fn open_dashboard_tab(&mut self) {
+        self.active_tab = ActiveTab::Dashboard;
+        self.refresh_dashboard().await;
+    }
+
+    /// Switch to the Favorites tab, fetching the favorites every time so marks from other clients show up
+    async fn open_favorites_tab(&mut self) {
+        self.active_tab = ActiveTab::Favorites;
+        self.refresh_favorites().await;
+    }
+
+    /// Switch to the Sessions tab, fetching the sessions right away
+    fn open_sessions_tab(&mut self) {
+        self.active_tab = ActiveTab::Sessions;
+        self.refresh_sessions(true);
+    }
+
+    /// Switch to the Playlists tab, fetching the playlists every time so edits from other clients show up
+    async fn open_playlists_tab(&mut self) {
+        self.active_tab = ActiveTab::Playlists;
+        self.refresh_playlists().await;
+        if self.open_playlist_id.is_empty() {
+            self.playlists_section = PlaylistsSection::Playlists;
+        }
+    }
+
+    async fn open_syncplay_popup(&mut self) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        self.syncplay_groups = syncplay::groups(client).await.unwrap_or_default();
+        self.popup = Some(Popup::new(PopupAction::SyncPlay));
+    }
+
+    async fn open_add_to_playlist_popup(&mut self, track_ids: Vec<String>) {
+        if self.playlists.is_empty() {
+            self.refresh_playlists().await;
+        }
+        let mut popup = Popup::new(PopupAction::AddToPlaylist);
+        popup.track_ids = track_ids;
+        self.popup = Some(popup);
+    }
+
+    async fn handle_popup_key_event(&mut self, key_event: KeyEvent) {
+        let mut popup = match self.popup.take() {
+            Some(popup) => popup,
+            None => return,
+        };
+
+        if key_event.code == KeyCode::Esc {
+            return;
+        }
+
+        match popup.action {
+            PopupAction::AddToPlaylist => match key_event.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    // the first item is "+ New playlist"
+                    if selected < self.playlists.len() {
+                        popup.selected.select(Some(selected + 1));
+                    }
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    if selected > 0 {
+                        popup.selected.select(Some(selected - 1));
+                    }
+                }
+                KeyCode::Enter => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    if selected == 0 {
+                        popup.action = PopupAction::CreatePlaylist;
+                    } else if let Some(playlist) = self.playlists.get(selected - 1) {
+                        let playlist_id = playlist.id.clone();
+                        if let Some(client) = &self.client {
+                            if client.add_to_playlist(&playlist_id, &popup.track_ids).await.is_ok() {
+                                if let Some(playlist) = self.playlists.get_mut(selected - 1) {
+                                    playlist.child_count += popup.track_ids.len() as u64;
+                                }
+                                if playlist_id == self.open_playlist_id {
+                                    self.open_playlist(&playlist_id).await;
+                                    self.playlists_section = PlaylistsSection::Tracks;
+                                }
+                            }
+                        }
+                        return;
+                    }
+                }
+                _ => {}
+            },
+            PopupAction::SyncPlay => match key_event.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    // the first item is "+ New group" or "- Leave group"
+                    if selected < self.syncplay_groups.len() {
+                        popup.selected.select(Some(selected + 1));
+                    }
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    if selected > 0 {
+                        popup.selected.select(Some(selected - 1));
+                    }
+                }
+                KeyCode::Enter => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    if selected == 0 && self.syncplay.is_some() {
+                        self.leave_syncplay_group();
+                    } else if selected == 0 {
+                        popup.action = PopupAction::CreateSyncPlayGroup;
+                        self.popup = Some(popup);
+                    } else if let Some(group) = self.syncplay_groups.get(selected - 1) {
+                        self.join_syncplay_group(&group.id);
+                    }
+                    return;
+                }
+                _ => {}
+            },
+            PopupAction::CreatePlaylist | PopupAction::RenamePlaylist | PopupAction::CreateSyncPlayGroup => match key_event.code {
+                KeyCode::Char(c) => {
+                    popup.input.push(c);
+                }
+                KeyCode::Backspace => {
+                    popup.input.pop();
+                }
+                KeyCode::Delete => {
+                    popup.input.clear();
+                }
+                KeyCode::Enter => {
+                    let name = popup.input.trim().to_string();
+                    if name.is_empty() {
+                        self.popup = Some(popup);
+                        return;
+                    }
+                    if popup.action == PopupAction::CreateSyncPlayGroup {
+                        self.create_syncplay_group(&name);
+                        return;
+                    }
+                    if let Some(client) = &self.client {
+                        if popup.action == PopupAction::CreatePlaylist {
+                            if let Ok(id) = client.create_playlist(&name, &popup.track_ids).await {
+                                self.refresh_playlists().await;
+                                if let Some(index) = self.playlists.iter().position(|p| p.id == id) {
+                                    self.selected_playlist.select(Some(index));
+                                }
+                            }
+                        } else if client.rename_playlist(&popup.playlist_id, &name).await.is_ok() {
+                            if let Some(playlist) = self.playlists.iter_mut().find(|p| p.id == popup.playlist_id) {
+                                playlist.name = name;
+                            }
+                        }
+                    }
+                    return;
+                }
+                _ => {}
+            },
+            PopupAction::TrackColumns => match key_event.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    if selected < TrackColumn::ALL.len() - 1 {
+                        popup.selected.select(Some(selected + 1));
+                    }
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    let selected = popup.selected.selected().unwrap_or(0);
+                    if selected > 0 {
+                        popup.selected.select(Some(selected - 1));
+                    }
+                }
+                // the popup stays open, so several columns can be toggled at once
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    let column = TrackColumn::ALL[popup.selected.selected().unwrap_or(0)];
+                    if self.track_columns.contains(&column) {
+                        self.track_columns.retain(|c| *c != column);
+                    } else {
+                        // keep the order of TrackColumn::ALL
+                        self.track_columns.push(column);
+                        self.track_columns = TrackColumn::ALL
+                            .into_iter()
+                            .filter(|c| self.track_columns.contains(c))
+                            .collect();
+                    }
+                }
+                _ => {}
+            },
+        }
+
+        self.popup = Some(popup);
+    }
+
     fn handle_mouse_event(&mut self, _mouse_event: crossterm::event::MouseEvent) {
         // println!("Mouse event: {:?}", _mouse_event);
     }
@@ -1002,10 +2198,17 @@ impl App {
 /// Enum types for section switching
 
 /// Active global tab
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ActiveTab {
     Library,
     Search,
+    Playlists,
+    Downloads,
+    Sessions,
+    Favorites,
+    Dashboard,
+    Genres,
+    Albums,
 }
 impl Default for ActiveTab {
     fn default() -> Self {
@@ -1038,4 +2241,56 @@ impl Default for SearchSection {
     fn default() -> Self {
         SearchSection::Artists
     }
+}
+
+/// Dashboard - active "section", in the order Tab cycles through them
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DashboardSection {
+    #[default]
+    RecentlyAdded,
+    RecentlyPlayed,
+    MostPlayed,
+    FavoriteAlbums,
+}
+impl DashboardSection {
+    pub fn next(self) -> Self {
+        match self {
+            DashboardSection::RecentlyAdded => DashboardSection::RecentlyPlayed,
+            DashboardSection::RecentlyPlayed => DashboardSection::MostPlayed,
+            DashboardSection::MostPlayed => DashboardSection::FavoriteAlbums,
+            DashboardSection::FavoriteAlbums => DashboardSection::RecentlyAdded,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        match self {
+            DashboardSection::RecentlyAdded => DashboardSection::FavoriteAlbums,
+            DashboardSection::RecentlyPlayed => DashboardSection::RecentlyAdded,
+            DashboardSection::MostPlayed => DashboardSection::RecentlyPlayed,
+            DashboardSection::FavoriteAlbums => DashboardSection::MostPlayed,
+        }
+    }
+}
+
+/// Genres - active "section"
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GenresSection {
+    #[default]
+    Genres,
+    Artists,
+    Albums,
+}
+/// Albums - active "section"
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AlbumsSection {
+    #[default]
+    Albums,
+    Tracks,
+}
+/// Playlists - active "section"
+#[derive(Debug, PartialEq, Default)]
+pub enum PlaylistsSection {
+    #[default]
+    Playlists,
+    Tracks,
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
-use crate::tui::App;
+use crate::client::{MediaStream, NormalizationMode};
+use crate::tui::{App, Repeat, TrackColumn, TrackRow};
 use crate::keyboard::{*};
 
-use souvlaki::MediaMetadata;
 use ratatui_image::{StatefulImage, Resize};
 use layout::Flex;
 use ratatui::{
@@ -36,16 +36,6 @@ impl App {
             .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
             .split(outer_layout[1]);
         
-        let show_lyrics = self.lyrics.as_ref().map_or(false, |(_, lyrics, _)| !lyrics.is_empty());
-        let right = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(if show_lyrics {
-                vec![Constraint::Percentage(68), Constraint::Percentage(32)]
-            } else {
-                vec![Constraint::Min(3), Constraint::Percentage(100)]
-            })
-            .split(outer_layout[2]);
-    
         let artist_block = match self.active_section {
             ActiveSection::Artists => Block::new()
                 .borders(Borders::ALL)
@@ -78,8 +68,15 @@ impl App {
                 artist.name.to_lowercase().contains(&self.artists_search_term.to_lowercase())
             })
             .map(|artist| {
+                let favorite = if artist.user_data.is_favorite {
+                    Span::styled(" ♥", Style::default().fg(Color::Red))
+                } else {
+                    Span::from("")
+                };
                 if self.playlist.iter().map(|song| song.artist_items.clone()).flatten().any(|a| a.id == artist.id) {
-                    return ListItem::new(artist.name.as_str())
+                    let mut item = Text::from(artist.name.as_str());
+                    item.push_span(favorite);
+                    ListItem::new(item)
                         .style(Style::default().fg(Color::Blue))
                 } else {
                     let mut item = Text::default();
@@ -87,7 +84,8 @@ impl App {
                     if artist.jellyfintui_recently_added {
                         item.push_span(Span::styled(" ★", Style::default().fg(Color::Yellow)));
                     }
-                    return ListItem::new(item)
+                    item.push_span(favorite);
+                    ListItem::new(item)
                 }
             })
             .collect::<Vec<ListItem>>();
@@ -125,24 +123,36 @@ impl App {
                 .fg(Color::Black)
                 .add_modifier(Modifier::BOLD),
         };
-        let items = self
-            .tracks
-            .iter()
-            // if search_term is not empty we filter the tracks
-            .filter(|track| {
-                if self.tracks_search_term.is_empty() {
-                    return true;
-                }
-                track.name.to_lowercase().contains(&self.tracks_search_term.to_lowercase()) && track.id != "_album_"
-            })
-            .map(|track| {
-                if track.id == "_album_" {
-                    // this is the dummy that symbolizes the name of the album
-                    return ListItem::new(track.name.as_str())
-                        .style(Style::default().fg(Color::White)
-                        .add_modifier(Modifier::BOLD));
-                }
-                let title = format!("{}", track.name);
+        let rows = self
+            .track_rows()
+            .into_iter()
+            .map(|row| {
+                let track = match row {
+                    TrackRow::Song(i) => &self.tracks[i],
+                    TrackRow::Album(first) => {
+                        let album = &self.tracks[first];
+                        let collapsed = self.collapsed_albums.contains(&album.album_id);
+                        let mut cells = vec![
+                            Cell::from(if collapsed { "▸" } else { "▾" }),
+                            Cell::from(format!("{} ({})", album.album, album.production_year)),
+                        ];
+                        cells.extend(self.track_columns.iter().map(|column| match column {
+                            TrackColumn::Favorite if self.favorite_album_ids.contains(&album.album_id) => {
+                                Cell::from("♥").style(Style::default().fg(Color::Red))
+                            }
+                            _ => Cell::from(""),
+                        }));
+                        if collapsed {
+                            cells.push(
+                                Cell::from(format!("{} tracks", self.tracks[first..].iter().take_while(|track| track.album_id == album.album_id).count()))
+                                    .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)),
+                            );
+                        }
+                        return Row::new(cells)
+                            .style(Style::default().fg(Color::White)
+                            .add_modifier(Modifier::BOLD));
+                    }
+                };
                 // track.run_time_ticks is in microseconds
                 let seconds = (track.run_time_ticks / 1_000_0000) % 60;
                 let minutes = (track.run_time_ticks / 1_000_0000 / 60) % 60;
@@ -151,8 +161,8 @@ impl App {
                     0 => String::from(""),
                     _ => format!("{}:", hours),
                 };
-    
-                let mut time_span_text = format!("  {}{:02}:{:02}", hours_optional_text, minutes, seconds);
+
+                let mut time_span_text = format!("{}{:02}:{:02}", hours_optional_text, minutes, seconds);
                 // push track.parent_index_number as CD1, CD2, etc
                 if track.parent_index_number > 0 {
                     time_span_text.push_str(
@@ -162,58 +172,58 @@ impl App {
                 if track.has_lyrics{
                     time_span_text.push_str(" (l)");
                 }
-                let index = Span::styled(
-                    format!("{}. ", track.index_number),
-                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                let title_color = if track.id == self.active_song_id { Color::Blue } else { Color::White };
+                let mut cells = vec![
+                    Cell::from(format!("{}.", track.index_number))
+                        .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)),
+                    Cell::from(track.name.as_str()).style(Style::default().fg(title_color)),
+                ];
+                cells.extend(self.track_columns.iter().map(|column| {
+                    let style = match column {
+                        TrackColumn::Favorite => Style::default().fg(Color::Red),
+                        _ => Style::default().fg(Color::DarkGray),
+                    };
+                    Cell::from(column.value(track)).style(style)
+                }));
+                cells.push(
+                    Cell::from(time_span_text)
+                        .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)),
                 );
-                if track.id == self.active_song_id {
-                    let mut time: Text = Text::default();
-                    time.push_span(
-                        Span::styled(
-                            format!("{}{}", index, title),
-                            Style::default().fg(Color::Blue),
-                        )
-                    );
-                    time.push_span(
-                        Span::styled(
-                            time_span_text,
-                            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
-                        )
-                    );
-                    ListItem::new(time)
-                        .style(Style::default().fg(Color::Blue))
-    
-                } else {
-                    let mut time: Text = Text::from(index);
-                    time.push_span(
-                        Span::styled(
-                            title,
-                            Style::default().fg(Color::White),
-                        )
-                    );
-                    time.push_span(
-                        Span::styled(
-                            time_span_text,
-                            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
-                        )
-                    );
-                    ListItem::new(time)
-                }
+                Row::new(cells)
             })
-            .collect::<Vec<ListItem>>();
+            .collect::<Vec<Row>>();
+
+        let mut widths = vec![Constraint::Length(4), Constraint::Fill(1)];
+        widths.extend(self.track_columns.iter().map(|column| Constraint::Length(column.width())));
+        widths.push(Constraint::Length(15));
+
+        let mut header = vec![Cell::from("#"), Cell::from("Title")];
+        header.extend(self.track_columns.iter().map(|column| Cell::from(column.title())));
+        header.push(Cell::from("Length"));
+
         let track_instructions = Title::from(Line::from(vec![
             " Play/Pause ".white().into(),
             "<Space>".blue().bold(),
             " Seek+5s ".white().into(),
-            "<S>".blue().bold(),
+            "<s>".blue().bold(),
             " Seek-5s ".white().into(),
-            "<R>".blue().bold(),
+            "<r>".blue().bold(),
             " Next Section ".white().into(),
             "<Tab>".blue().bold(),
+            " Fold album ".white().into(),
+            "<c>".blue().bold(),
+            " Shuffle album ".white().into(),
+            "<S>".blue().bold(),
+            " Columns ".white().into(),
+            "<C>".blue().bold(),
             " Quit ".white().into(),
             "<Q> ".blue().bold(),
         ]));
-        let list = List::new(items)
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(header)
+                    .style(Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD)),
+            )
             .block(
                 track_block
                     .title(if self.tracks_search_term.is_empty() {
@@ -227,8 +237,7 @@ impl App {
             .highlight_style(
                 track_highlight_style
             )
-            .scroll_padding(10)
-            .repeat_highlight_symbol(true);
+            .highlight_spacing(HighlightSpacing::Always);
     
         if self.tracks.len() == 0 {
             let message_paragraph = Paragraph::new("jellyfin-tui")
@@ -242,7 +251,7 @@ impl App {
             frame.render_widget(message_paragraph, center[0]);
         } else {
             frame.render_widget(Clear, center[0]);
-            frame.render_stateful_widget(list, center[0], &mut self.selected_track);
+            frame.render_stateful_widget(table, center[0], &mut self.selected_track);
         }
     
         // change section Title to 'Searching: TERM' if locally searching
@@ -274,6 +283,13 @@ impl App {
             }
         }
     
+        self.render_player(frame, center[1]);
+
+        self.render_library_right(frame, outer_layout[2]);
+    }
+
+    /// The player bar: cover art, the current song, progress and metadata
+    pub fn render_player(&mut self, frame: &mut Frame, area: Rect) {
         // currently playing song name. We can get this easily, we have the playlist and the current index
         let current_song = match self
             .playlist
@@ -290,44 +306,11 @@ impl App {
             None => String::from("No song playing"),
         };
     
-        // update mpris metadata
-        if self.current_playback_state.current_index != self.current_playback_state.last_index {
-            let metadata = match self
-                .playlist
-                .get(self.current_playback_state.current_index as usize)
-            {
-                Some(song) => {
-                    let metadata = MediaMetadata {
-                        title: Some(song.name.as_str()),
-                        artist: Some(song.artist.as_str()),
-                        album: Some(song.album.as_str()),
-                        cover_url: None,
-                        duration: None,
-                    };
-                    // TODO add cover art to mpris
-                    // if let Some(ref cover_art) = self.cover_art {
-                    //     metadata.cover_url = Some(cover_art
-                    // }
-                    metadata
-                }
-                None => MediaMetadata {
-                    title: None,
-                    artist: None,
-                    album: None,
-                    cover_url: None,
-                    duration: None,
-                },
-            };
-
-            if let Some(ref mut controls) = self.controls {
-                let _ = controls.set_metadata(metadata);
-            }
-        }
         let bottom = Block::default()
             .borders(Borders::ALL)
             .padding(Padding::new(0, 0, 0, 0));
-        let inner = bottom.inner(center[1]);
-        frame.render_widget(bottom, center[1]);
+        let inner = bottom.inner(area);
+        frame.render_widget(bottom, area);
     
         // split the bottom into two parts
         let bottom_split = Layout::default()
@@ -399,13 +382,19 @@ impl App {
             progress_bar_area[1],
         );
     
+        let url = self
+            .playlist
+            .get(self.current_playback_state.current_index as usize)
+            .map(|song| song.url.as_str())
+            .unwrap_or("");
         let metadata = match self.metadata {
             Some(ref metadata) => format!(
-                "{} - {} Hz - {} channels - {} kbps",
+                "{} - {} Hz - {} channels - {} kbps - {}",
                 metadata.codec.as_str(),
                 metadata.sample_rate,
                 metadata.channels,
                 metadata.bit_rate / 1000,
+                playback_method(url, metadata),
             ),
             None => String::from("No metadata available"),
         };
@@ -457,6 +446,27 @@ impl App {
                 }
             }
         }
+
+        // shuffle, repeat, auto-DJ and normalization indicators, under the time
+        let repeat = match self.repeat {
+            Repeat::None => "↻ ".dark_gray(),
+            Repeat::All => "↻ ".white().bold(),
+            Repeat::One => "↻1".white().bold(),
+        };
+        let shuffle = if self.shuffle { "⇄ ".white().bold() } else { "⇄ ".dark_gray() };
+        let auto_dj = if self.auto_dj { " ∞".white().bold() } else { " ∞".dark_gray() };
+        let normalization = match self.client.as_ref().map(|client| client.normalization.mode) {
+            Some(mode) if mode != NormalizationMode::Off => format!(" ≈{}", mode.title()).white().bold(),
+            _ => " ≈off".dark_gray(),
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![shuffle, repeat, auto_dj, normalization])).centered().block(
+                Block::bordered()
+                    .borders(Borders::NONE)
+                    .padding(Padding::new(0, 0, 1, 0)),
+            ),
+            progress_bar_area[2],
+        );
     
         match self.current_playback_state.duration {
             0.0 => {
@@ -490,9 +500,23 @@ impl App {
                 );
             }
         }
-    
+    }
+
+    /// The right column of the library: lyrics on top, queue below
+    pub fn render_library_right(&mut self, frame: &mut Frame, area: Rect) {
+        let show_lyrics = self.lyrics.as_ref().map_or(false, |(_, lyrics, _)| !lyrics.is_empty());
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(if show_lyrics {
+                vec![Constraint::Percentage(68), Constraint::Percentage(32)]
+            } else {
+                vec![Constraint::Min(3), Constraint::Percentage(100)]
+            })
+            .split(area);
+
+        // the library sections only have focus while the library tab is open
         let lyrics_block = match self.active_section {
-            ActiveSection::Lyrics => Block::new()
+            ActiveSection::Lyrics if self.active_tab == ActiveTab::Library => Block::new()
                 .borders(Borders::ALL)
                 .border_style(style::Color::Blue)
                 ,
@@ -574,7 +598,7 @@ impl App {
         }
     
         let queue_block = match self.active_section {
-            ActiveSection::Queue => Block::new()
+            ActiveSection::Queue if self.active_tab == ActiveTab::Library => Block::new()
                 .borders(Borders::ALL)
                 .border_style(style::Color::Blue),
             _ => Block::new()
@@ -585,10 +609,38 @@ impl App {
         let items = self
             .playlist
             .iter()
-            .map(|song| song.name.as_str())
-            .collect::<Vec<&str>>();
+            .enumerate()
+            .map(|(i, song)| {
+                let mut item = Text::from(song.name.as_str());
+                if song.is_favorite {
+                    item.push_span(Span::styled(" ♥", Style::default().fg(Color::Red)));
+                }
+                if i as i64 == self.current_playback_state.current_index {
+                    ListItem::new(item)
+                        .style(Style::default().fg(Color::Blue))
+                } else {
+                    ListItem::new(item)
+                }
+            })
+            .collect::<Vec<ListItem>>();
+        let queue_instructions = Title::from(Line::from(vec![
+            " Play ".white().into(),
+            "<Enter>".blue().bold(),
+            " Remove ".white().into(),
+            "<D>".blue().bold(),
+            " Move ".white().into(),
+            "<J/K> ".blue().bold(),
+        ]));
         let list = List::new(items)
-            .block(queue_block.title("Queue"))
+            .block(
+                if self.active_section == ActiveSection::Queue && self.active_tab == ActiveTab::Library {
+                    queue_block
+                        .title("Queue")
+                        .title(queue_instructions.alignment(Alignment::Center).position(Position::Bottom))
+                } else {
+                    queue_block.title("Queue")
+                }
+            )
             .highlight_symbol(">>")
             .highlight_style(
                 Style::default()
@@ -620,4 +672,56 @@ impl App {
           ])
           .split(popup_layout[1])[1]
     }
-}
\ No newline at end of file
+}
+
+/// How the song reaches mpv, judging by the url it was queued with
+fn playback_method(url: &str, metadata: &MediaStream) -> String {
+    if !url.starts_with("http") {
+        return String::from("offline");
+    }
+    let param = |key: &str| {
+        url.split(['?', '&'])
+            .find_map(|pair| pair.strip_prefix(key).and_then(|value| value.strip_prefix('=')))
+    };
+    let bitrate = param("MaxStreamingBitrate").and_then(|bitrate| bitrate.parse::<u64>().ok());
+    match (param("AudioCodec"), bitrate) {
+        (Some(codec), Some(bitrate)) if metadata.codec != codec || metadata.bit_rate > bitrate => {
+            format!("transcoding to {} {} kbps", codec, bitrate / 1000)
+        }
+        _ => String::from("direct play"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(codec: &str, bit_rate: u64) -> MediaStream {
+        MediaStream {
+            codec: codec.to_string(),
+            bit_rate,
+            channels: 2,
+            sample_rate: 44100,
+            type_: String::from("Audio"),
+        }
+    }
+
+    #[test]
+    fn local_files_are_offline() {
+        assert_eq!(playback_method("/home/user/.cache/jellyfin-tui/downloads/song", &stream("flac", 900000)), "offline");
+    }
+
+    #[test]
+    fn transcoding_when_the_codec_or_bitrate_differ() {
+        let url = "https://jellyfin.example/Audio/x/universal?UserId=y&AudioCodec=opus&MaxStreamingBitrate=320000";
+        assert_eq!(playback_method(url, &stream("flac", 900000)), "transcoding to opus 320 kbps");
+        assert_eq!(playback_method(url, &stream("opus", 500000)), "transcoding to opus 320 kbps");
+    }
+
+    #[test]
+    fn direct_play_when_nothing_changes() {
+        let url = "https://jellyfin.example/Audio/x/universal?UserId=y&AudioCodec=opus&MaxStreamingBitrate=320000";
+        assert_eq!(playback_method(url, &stream("opus", 128000)), "direct play");
+        assert_eq!(playback_method("https://jellyfin.example/Items/x/Download?api_key=z", &stream("flac", 900000)), "direct play");
+    }
+}
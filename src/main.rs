@@ -4,9 +4,22 @@ mod keyboard;
 mod mpris;
 mod library;
 mod search;
+mod playlists;
+mod popup;
+mod downloads;
+mod cache;
+mod remote;
+mod sessions;
+mod syncplay;
+mod favorites;
+mod dashboard;
+mod radio;
+mod genres;
+mod albums;
+mod player;
 use tokio;
 
-use std::{io::stdout, vec};
+use std::io::stdout;
 use std::env;
 // use serde_yaml::Value;
 // use std::{collections::HashMap};
@@ -43,32 +56,44 @@ async fn main() {
     );
 
     let client = client::Client::new().await;
-    if client.access_token.is_empty() {
+    if client.access_token.is_empty() && !client.offline {
         println!("Failed to authenticate. Exiting...");
         return;
     }
 
-    println!("[OK] Authenticated!");
+    if client.offline {
+        println!("[OK] Using the local library snapshot and downloaded tracks");
+    } else {
+        println!("[OK] Authenticated!");
+    }
 
-    let mut artists = match client.artists(String::from("")).await {
-        Ok(artists) => artists,
-        Err(e) => {
-            println!("[!!] Failed to get artists: {:?}", e);
-            return;
-        }
+    // the cached artists show up right away, the app refreshes them in the background
+    let cached_artists = match client.offline {
+        true => None,
+        false => cache::load_artists().filter(|artists| !artists.is_empty()),
     };
-
-    let new_artists = match client.new_artists().await {
-        Ok(artists) => artists,
-        Err(_e) => {
-            vec![]
+    let from_cache = cached_artists.is_some();
+
+    let mut artists = match cached_artists {
+        Some(artists) => artists,
+        None => {
+            if let Ok(date_created) = client.latest_date_created().await {
+                cache::validate(&client.base_url, &date_created);
+            }
+            match client.library_artists().await {
+                Ok(artists) => artists,
+                Err(e) => {
+                    println!("[!!] Failed to get artists: {:?}", e);
+                    return;
+                }
+            }
         }
     };
 
-    for artist in &mut artists {
-        if new_artists.contains(&artist.id) {
-            artist.jellyfintui_recently_added = true;
-        }
+    // offline, only artists we have downloaded something from can be played
+    if client.offline {
+        let downloaded = downloads::downloaded_artist_ids();
+        artists.retain(|artist| downloaded.contains(&artist.id));
     }
 
     enable_raw_mode().unwrap();
@@ -79,6 +104,9 @@ async fn main() {
 
     let mut app = tui::App::default();
     app.init(artists).await;
+    if from_cache {
+        app.refresh_library();
+    }
 
     terminal.clear().unwrap();
 
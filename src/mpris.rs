@@ -1,6 +1,8 @@
-use crate::tui::{App, MpvState};
-use souvlaki::{MediaControlEvent, MediaControls, PlatformConfig};
-use std::sync::{Arc, Mutex};
+use crate::cache;
+use crate::player::{PlayerCommand, MAX_VOLUME};
+use crate::tui::{App, Repeat, Song};
+use souvlaki::{LoopStatus, MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig, SeekDirection};
+use std::time::{Duration, Instant};
 
 // linux only, macos requires a window and windows is unsupported
 pub fn mpris() -> Result<MediaControls, Box<dyn std::error::Error>> {
@@ -23,74 +25,275 @@ pub fn mpris() -> Result<MediaControls, Box<dyn std::error::Error>> {
     }
 }
 
+impl From<Repeat> for LoopStatus {
+    fn from(repeat: Repeat) -> Self {
+        match repeat {
+            Repeat::None => LoopStatus::None,
+            Repeat::All => LoopStatus::Playlist,
+            Repeat::One => LoopStatus::Track,
+        }
+    }
+}
+
+/// Finds the item id in a Jellyfin url, either a web ui link (`.../web/#/details?id=...`)
+/// or an api path (`.../Items/<id>`)
+fn jellyfin_item_id(uri: &str) -> Option<String> {
+    let is_id = |id: &str| id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit());
+    if let Some((_, query)) = uri.split_once('?') {
+        if let Some(id) = query.split('&').find_map(|pair| pair.strip_prefix("id=")) {
+            return Some(id.to_string()).filter(|id| is_id(id));
+        }
+    }
+    let path = uri.split(['?', '#']).next().unwrap_or("");
+    path.rsplit('/').find(|segment| is_id(segment)).map(String::from)
+}
+
 impl App {
 
-    /// Registers the media controls to the MpvState. Called after each mpv thread re-init.
-    pub fn register_controls(&mut self, mpv_state: Arc<Mutex<MpvState>>) {
+    /// Registers the media controls. Their events are handled once per frame, see `handle_mpris_events`
+    pub fn register_controls(&mut self) {
+        let sender = self.mpris_events_sender.clone();
         match self.controls {
             Some(ref mut controls) => {
                 controls
                     .attach(move |event: MediaControlEvent| {
-                        let lock = mpv_state.clone();
-                        let mut mpv = match lock.lock() {
-                            Ok(mpv) => mpv,
-                            Err(_) => {
-                                return;
-                            }
-                        };
-
-                        mpv.mpris_events.push(event.clone());
-
-                        drop(mpv);
+                        let _ = sender.send(event);
                     })
                     .unwrap();
             }
             None => {}
         }
     }
+
+    /// Keeps the MPRIS now-playing card current: the metadata when the song changes,
+    /// the status when it changes and the position about once a second
+    pub fn update_mpris(&mut self) {
+        let controls = match self.controls.as_mut() {
+            Some(controls) => controls,
+            None => return,
+        };
+        let song = self.playlist.get(self.current_playback_state.current_index as usize);
+
+        let song_id = song.map(|song| song.id.as_str()).unwrap_or("");
+        let song_changed = song_id != self.mpris_song_id;
+        if song_changed {
+            match song {
+                Some(song) => {
+                    let artists = song.artist_items.iter().map(|a| a.name.as_str()).collect::<Vec<&str>>().join(", ");
+                    let cover_url = cache::cover_file(&song.parent_id, &song.album_image_tag)
+                        .filter(|path| path.exists())
+                        .map(|path| format!("file://{}", path.display()));
+                    let _ = controls.set_metadata(MediaMetadata {
+                        title: Some(song.name.as_str()),
+                        artist: Some(if artists.is_empty() { song.artist.as_str() } else { artists.as_str() }),
+                        album: Some(song.album.as_str()),
+                        album_artist: Some(song.artist.as_str()),
+                        track_number: Some(song.index_number as i32).filter(|n| *n > 0),
+                        cover_url: cover_url.as_deref(),
+                        // run_time_ticks are 100 nanoseconds
+                        duration: Some(Duration::from_nanos(song.run_time_ticks * 100)).filter(|d| !d.is_zero()),
+                    });
+                }
+                None => {
+                    let _ = controls.set_metadata(MediaMetadata::default());
+                }
+            }
+            self.mpris_song_id = song_id.to_string();
+        }
+
+        let current_time = (self.current_playback_state.duration * self.current_playback_state.percentage / 100.0).max(0.0);
+        let position = MediaPosition(Duration::from_secs_f64(current_time));
+
+        // a position away from where playback alone would have taken it is a seek. Across a song
+        // change or a pause toggle the last position says nothing, so start over from here
+        let (last_time, last_frame) = self.mpris_position;
+        let expected = if self.paused { last_time } else { last_time + last_frame.elapsed().as_secs_f64() };
+        let pause_changed = self.paused != self.mpris_paused;
+        if song.is_some() && !song_changed && !pause_changed && (current_time - expected).abs() > 2.0 {
+            let _ = controls.emit_seeked(position);
+        }
+        self.mpris_position = (current_time, Instant::now());
+        self.mpris_paused = self.paused;
+
+        if self.mpris_volume != Some(self.current_playback_state.volume) {
+            let _ = controls.set_volume(self.current_playback_state.volume as f64 / 100.0);
+            self.mpris_volume = Some(self.current_playback_state.volume);
+        }
+        // a Stop holds until playback resumes
+        if !self.paused {
+            self.mpris_stopped = false;
+        }
+        let playback = match song {
+            None => MediaPlayback::Stopped,
+            Some(_) if self.mpris_stopped => MediaPlayback::Stopped,
+            Some(_) if self.paused => MediaPlayback::Paused { progress: Some(position) },
+            Some(_) => MediaPlayback::Playing { progress: Some(position) },
+        };
+        let changed = match (&self.mpris_playback, &playback) {
+            (Some(MediaPlayback::Playing { progress: Some(last) }), MediaPlayback::Playing { progress: Some(now) })
+            | (Some(MediaPlayback::Paused { progress: Some(last) }), MediaPlayback::Paused { progress: Some(now) }) => {
+                last.0.abs_diff(now.0) >= Duration::from_secs(1)
+            }
+            (last, now) => last.as_ref() != Some(now),
+        };
+        if changed {
+            let _ = controls.set_playback(playback.clone());
+            self.mpris_playback = Some(playback);
+        }
+    }
+
     pub async fn handle_mpris_events(&mut self) {
-        let lock = self.mpv_state.clone();
-        let mut mpv = lock.lock().unwrap();
-        for event in mpv.mpris_events.iter() {
+        while let Ok(event) = self.mpris_events.try_recv() {
+            let current_time = self.current_playback_state.duration * self.current_playback_state.percentage / 100.0;
+            // the target of a relative seek, in seconds
+            let seek_target = match &event {
+                MediaControlEvent::Seek(direction) => Some((direction, 5.0)),
+                MediaControlEvent::SeekBy(direction, offset) => Some((direction, offset.as_secs_f64())),
+                _ => None,
+            }
+            .map(|(direction, offset)| match direction {
+                SeekDirection::Forward => current_time + offset,
+                SeekDirection::Backward => (current_time - offset).max(0.0),
+            });
+            // in a SyncPlay group, playback is driven by the group
+            let handled_by_group = match &event {
+                MediaControlEvent::Toggle => self.syncplay_pause(!self.paused),
+                MediaControlEvent::Play => self.syncplay_pause(false),
+                MediaControlEvent::Pause => self.syncplay_pause(true),
+                MediaControlEvent::Next => self.syncplay_skip(true),
+                MediaControlEvent::Previous => self.syncplay_skip(false),
+                MediaControlEvent::SetPosition(position) => self.syncplay_seek(position.0.as_secs_f64()),
+                MediaControlEvent::Seek(_) | MediaControlEvent::SeekBy(..) => {
+                    self.syncplay_seek(seek_target.unwrap_or(current_time))
+                }
+                _ => false,
+            };
+            if handled_by_group {
+                continue;
+            }
             match event {
                 MediaControlEvent::Toggle => {
-                    self.paused = mpv.mpv.get_property("pause").unwrap_or(false);
-                    if self.paused {
-                        let _ = mpv.mpv.unpause();
-                    } else {
-                        let _ = mpv.mpv.pause();
-                    }
                     self.paused = !self.paused;
+                    self.player.send(PlayerCommand::SetProperty("pause", self.paused.into()));
                 }
                 MediaControlEvent::Next => {
-                    let client = self.client.as_ref().unwrap();
-                    let _ = client.stopped(
-                        self.active_song_id.clone(),
-                        // position ticks
-                        (self.current_playback_state.duration * self.current_playback_state.percentage * 100000.0) as u64,
-                    );
-                    let _ = mpv.mpv.playlist_next_force();
+                    if let Some(client) = &self.client {
+                        let _ = client.stopped(
+                            self.active_song_id.clone(),
+                            // position ticks
+                            (self.current_playback_state.duration * self.current_playback_state.percentage * 100000.0) as u64,
+                        ).await;
+                    }
+                    self.player.send(PlayerCommand::Next);
                 }
                 MediaControlEvent::Previous => {
-                    let current_time = self.current_playback_state.duration * self.current_playback_state.percentage / 100.0;
                     if current_time > 5.0 {
-                        let _ = mpv.mpv.seek_absolute(0.0);
+                        self.player.send(PlayerCommand::Seek(0.0));
                     } else {
-                        let _ = mpv.mpv.playlist_previous_force();
+                        self.player.send(PlayerCommand::Previous);
                     }
                 }
                 MediaControlEvent::Stop => {
-                    // let _ = mpv.mpv.stop();
+                    // keep the queue, so playback can be resumed
+                    self.player.send(PlayerCommand::SetProperty("pause", true.into()));
+                    self.player.send(PlayerCommand::Seek(0.0));
+                    self.paused = true;
+                    self.mpris_stopped = true;
                 }
                 MediaControlEvent::Play => {
-                    let _ = mpv.mpv.unpause();
+                    self.player.send(PlayerCommand::SetProperty("pause", false.into()));
+                    self.paused = false;
                 }
                 MediaControlEvent::Pause => {
-                    let _ = mpv.mpv.pause();
+                    self.player.send(PlayerCommand::SetProperty("pause", true.into()));
+                    self.paused = true;
+                }
+                MediaControlEvent::SetPosition(position) => {
+                    self.player.send(PlayerCommand::Seek(position.0.as_secs_f64()));
+                }
+                MediaControlEvent::Seek(_) | MediaControlEvent::SeekBy(..) => {
+                    if let Some(target) = seek_target {
+                        self.player.send(PlayerCommand::Seek(target));
+                    }
+                }
+                MediaControlEvent::SetVolume(volume) => {
+                    self.current_playback_state.volume = ((volume * 100.0).round() as i64).clamp(0, MAX_VOLUME);
+                    self.player.send(PlayerCommand::SetProperty("volume", self.current_playback_state.volume.into()));
+                }
+                MediaControlEvent::SetShuffle(shuffle) => {
+                    let _ = self.set_shuffle(shuffle);
+                }
+                MediaControlEvent::SetLoopStatus(status) => {
+                    let _ = self.set_repeat(match status {
+                        LoopStatus::None => Repeat::None,
+                        LoopStatus::Playlist => Repeat::All,
+                        LoopStatus::Track => Repeat::One,
+                    });
+                }
+                MediaControlEvent::OpenUri(uri) => {
+                    self.open_uri(&uri).await;
                 }
                 _ => {}
             }
         }
-        mpv.mpris_events.clear();
+    }
+
+    /// Plays the Jellyfin item a url points to: a track, or every track of an album,
+    /// playlist or artist
+    async fn open_uri(&mut self, uri: &str) {
+        let id = match jellyfin_item_id(uri) {
+            Some(id) => id,
+            None => return,
+        };
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        let mut tracks = client.tracks(std::slice::from_ref(&id)).await.unwrap_or_default();
+        if tracks.is_empty() {
+            // albums and playlists are folders of their tracks
+            tracks = client.album_tracks(&id).await.unwrap_or_default();
+        }
+        if tracks.is_empty() {
+            tracks = client.discography(&id).await.map(|discography| discography.items).unwrap_or_default();
+        }
+        if tracks.is_empty() {
+            return; // TODO: inform user of error
+        }
+        let songs = tracks.iter().map(|track| Song::from_track(track, client)).collect::<Vec<Song>>();
+        self.playlist = songs;
+        let _ = self.replace_playlist(); // TODO: inform user of error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn item_id_from_web_ui_link() {
+        let uri = format!("https://jellyfin.example/web/#/details?id={}&serverId=f00", ID);
+        assert_eq!(jellyfin_item_id(&uri), Some(ID.to_string()));
+    }
+
+    #[test]
+    fn item_id_from_query() {
+        let uri = format!("https://jellyfin.example/web/index.html?context=music&id={}", ID);
+        assert_eq!(jellyfin_item_id(&uri), Some(ID.to_string()));
+    }
+
+    #[test]
+    fn item_id_from_api_path() {
+        let uri = format!("https://jellyfin.example/Items/{}/Download?api_key=abc", ID);
+        assert_eq!(jellyfin_item_id(&uri), Some(ID.to_string()));
+    }
+
+    #[test]
+    fn item_id_rejects_what_is_not_an_id() {
+        assert_eq!(jellyfin_item_id("https://jellyfin.example/web/#/details?id=1234"), None);
+        assert_eq!(jellyfin_item_id("https://jellyfin.example/Items/0123456789abcdef0123456789abcdeg"), None);
+        assert_eq!(jellyfin_item_id("https://jellyfin.example/web/#/home"), None);
     }
 }
@@ -0,0 +1,268 @@
+use crate::tui::MpvPlaybackState;
+
+use libmpv::events::{Event, PropertyData};
+use libmpv::{FileState, Format, Mpv};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+/// The loudest the volume keys and MPRIS can go, in percent
+pub const MAX_VOLUME: i64 = 500;
+
+/// A song as mpv gets it: the url and the per-file options, see `App::file_options`
+pub struct PlayerFile {
+    pub url: String,
+    pub options: String,
+}
+
+/// A property value for `PlayerCommand::SetProperty`
+pub enum PlayerValue {
+    Flag(bool),
+    Int(i64),
+    Str(&'static str),
+}
+
+impl From<bool> for PlayerValue {
+    fn from(value: bool) -> Self {
+        PlayerValue::Flag(value)
+    }
+}
+
+impl From<i64> for PlayerValue {
+    fn from(value: i64) -> Self {
+        PlayerValue::Int(value)
+    }
+}
+
+impl From<&'static str> for PlayerValue {
+    fn from(value: &'static str) -> Self {
+        PlayerValue::Str(value)
+    }
+}
+
+/// What the ui asks of the player. Indices are positions in the mpv playlist, which mirrors `App::playlist`
+pub enum PlayerCommand {
+    /// Replace the queue, and start `percentage` into the song at `index`
+    Load { files: Vec<PlayerFile>, index: i64, percentage: f64, paused: bool },
+    /// Add songs to the end of the queue
+    Append(Vec<PlayerFile>),
+    /// Add songs so that the first one ends up at `index`
+    Insert(Vec<PlayerFile>, usize),
+    /// Keep only the current song, with `before` in front of it and `after` behind it
+    ReplaceAround { before: Vec<PlayerFile>, after: Vec<PlayerFile> },
+    Jump(usize),
+    Remove(usize),
+    /// mpv's `playlist-move`: the entry at `from` goes in front of the one at `to`
+    Move(usize, usize),
+    Next,
+    Previous,
+    Seek(f64), // absolute, in seconds
+    SetProperty(&'static str, PlayerValue),
+    /// Empty the queue
+    Stop,
+}
+
+/// The ui side of the player service. A single mpv instance lives on the player thread for the
+/// whole session: commands go in here, and `MpvPlaybackState` updates come back on the state channel
+pub struct Player {
+    commands: Sender<PlayerCommand>,
+    sent: u64,
+    mpv: Arc<Mpv>, // only to wake the player thread up
+}
+
+impl Player {
+    pub fn new(states: Sender<MpvPlaybackState>) -> Self {
+        let mpv = Mpv::new().expect("[XX] Failed to create mpv instance");
+        mpv.set_property("vo", "null").unwrap();
+        mpv.set_property("volume", 100).unwrap();
+        mpv.set_property("volume-max", MAX_VOLUME).unwrap(); // mpv rejects anything above this
+        mpv.set_property("prefetch-playlist", "yes").unwrap(); // gapless playback
+        let mpv = Arc::new(mpv);
+
+        let (commands, receiver) = channel();
+        let handle = mpv.clone();
+        thread::spawn(move || {
+            let _ = t_player(handle, receiver, states); // TODO: inform user of error
+        });
+
+        Player { commands, sent: 0, mpv }
+    }
+
+    pub fn send(&mut self, command: PlayerCommand) {
+        if self.commands.send(command).is_ok() {
+            self.sent += 1;
+            self.mpv.wakeup();
+        }
+    }
+
+    /// How many commands were sent. States the player sent before handling all of them are stale
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+}
+
+impl Drop for Player {
+    /// Ends the player thread, which only notices the closed channel once it is woken up
+    fn drop(&mut self) {
+        let (closed, _) = channel();
+        drop(std::mem::replace(&mut self.commands, closed));
+        self.mpv.wakeup();
+    }
+}
+
+fn load_files(mpv: &Mpv, files: &[PlayerFile], state: FileState) -> libmpv::Result<()> {
+    let files = files
+        .iter()
+        .map(|file| (file.url.as_str(), state, Some(file.options.as_str()).filter(|o| !o.is_empty())))
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Ok(());
+    }
+    mpv.playlist_load_files(&files)
+}
+
+fn playlist_move(mpv: &Mpv, from: usize, to: usize) -> libmpv::Result<()> {
+    mpv.command("playlist-move", &[&from.to_string(), &to.to_string()])
+}
+
+fn handle_command(mpv: &Mpv, command: PlayerCommand) -> libmpv::Result<()> {
+    match command {
+        PlayerCommand::Load { mut files, index, percentage, paused } => {
+            // resume the starting song where it was left off
+            if let Some(file) = files.get_mut(index.max(0) as usize).filter(|_| percentage > 0.0) {
+                let start = format!("start={}%", percentage);
+                file.options = if file.options.is_empty() { start } else { format!("{},{}", file.options, start) };
+            }
+            // unlike playlist-clear, stop also drops the current song
+            mpv.command("stop", &[])?;
+            mpv.set_property("pause", paused)?;
+            load_files(mpv, &files, FileState::AppendPlay)?;
+            if index > 0 {
+                mpv.set_property("playlist-pos", index)?;
+            }
+        }
+        PlayerCommand::Append(files) => {
+            load_files(mpv, &files, FileState::Append)?;
+        }
+        PlayerCommand::Insert(files, index) => {
+            let appended_at = mpv.get_property::<i64>("playlist-count")? as usize;
+            load_files(mpv, &files, FileState::Append)?;
+            // mpv can only append, so move the new entries up to where they belong
+            for i in 0..files.len() {
+                playlist_move(mpv, appended_at + i, index + i)?;
+            }
+        }
+        PlayerCommand::ReplaceAround { before, after } => {
+            // leaves only the current entry, at index 0
+            mpv.playlist_clear()?;
+            load_files(mpv, &before, FileState::Append)?;
+            for i in 0..before.len() {
+                playlist_move(mpv, i + 1, i)?;
+            }
+            load_files(mpv, &after, FileState::Append)?;
+        }
+        PlayerCommand::Jump(index) => {
+            mpv.set_property("playlist-pos", index as i64)?;
+        }
+        PlayerCommand::Remove(index) => {
+            mpv.playlist_remove_index(index)?;
+        }
+        PlayerCommand::Move(from, to) => {
+            playlist_move(mpv, from, to)?;
+        }
+        PlayerCommand::Next => {
+            mpv.playlist_next_force()?;
+        }
+        PlayerCommand::Previous => {
+            mpv.playlist_previous_force()?;
+        }
+        PlayerCommand::Seek(seconds) => {
+            mpv.seek_absolute(seconds.max(0.0))?;
+        }
+        PlayerCommand::SetProperty(name, value) => match value {
+            PlayerValue::Flag(value) => mpv.set_property(name, value)?,
+            PlayerValue::Int(value) => mpv.set_property(name, value)?,
+            PlayerValue::Str(value) => mpv.set_property(name, value)?,
+        },
+        PlayerCommand::Stop => {
+            mpv.command("stop", &[])?;
+        }
+    }
+    Ok(())
+}
+
+/// The player thread. Sleeps until mpv has news or the ui sends a command, then sends
+/// one state for everything that happened
+fn t_player(
+    mpv: Arc<Mpv>,
+    commands: Receiver<PlayerCommand>,
+    states: Sender<MpvPlaybackState>,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let mut events = mpv.create_event_context();
+    events.disable_deprecated_events()?;
+    events.observe_property("percent-pos", Format::Double, 0)?;
+    events.observe_property("playlist-pos", Format::Int64, 0)?;
+    events.observe_property("duration", Format::Double, 0)?;
+    events.observe_property("volume", Format::Int64, 0)?;
+    events.observe_property("pause", Format::Flag, 0)?; // only to wake the ui, which tracks pause itself
+
+    let mut playback = MpvPlaybackState {
+        percentage: 0.0,
+        duration: 0.0,
+        current_index: 0,
+        volume: 100,
+        commands: 0,
+    };
+
+    loop {
+        // commands first, so the state sent below already reflects them
+        let mut changed = false;
+        loop {
+            match commands.try_recv() {
+                Ok(command) => {
+                    // stderr would garble the ui, and a failed command leaves mpv as it was
+                    let _ = handle_command(&mpv, command); // TODO: inform user of error
+                    playback.commands += 1;
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        // `Player::send` wakes this up
+        let mut event = events.wait_event(if changed { 0.0 } else { -1.0 });
+        while let Some(result) = event {
+            match result {
+                Ok(Event::PropertyChange { name, change, .. }) => {
+                    match (name, change) {
+                        ("percent-pos", PropertyData::Double(percentage)) => playback.percentage = percentage,
+                        ("playlist-pos", PropertyData::Int64(index)) => playback.current_index = index,
+                        ("duration", PropertyData::Double(duration)) => playback.duration = duration,
+                        ("volume", PropertyData::Int64(volume)) => playback.volume = volume,
+                        _ => {}
+                    }
+                    changed = true;
+                }
+                // the next song is starting, its position and duration are not known yet
+                Ok(Event::StartFile) => {
+                    playback.percentage = 0.0;
+                    playback.duration = 0.0;
+                    changed = true;
+                }
+                Ok(Event::EndFile(_)) => {
+                    changed = true;
+                }
+                _ => {}
+            }
+            // take everything that is already queued, and send one state for all of it
+            event = events.wait_event(0.0);
+        }
+
+        if changed {
+            // queue edits move the current song without always telling us
+            playback.current_index = mpv.get_property("playlist-pos").unwrap_or(playback.current_index);
+            let _ = states.send(playback.clone());
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::tui::App;
+use crate::keyboard::{*};
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+        Paragraph
+    },
+    prelude::*,
+    widgets::*,
+};
+
+impl App {
+    pub fn render_playlists(&mut self, app_container: Rect, frame: &mut Frame) {
+        let outer_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(20),
+                Constraint::Percentage(56),
+                Constraint::Percentage(24),
+            ])
+            .split(app_container);
+
+        let left = outer_layout[0];
+
+        let center = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
+            .split(outer_layout[1]);
+
+        let playlist_block = match self.playlists_section {
+            PlaylistsSection::Playlists => Block::new()
+                .borders(Borders::ALL)
+                .border_style(style::Color::Blue),
+            _ => Block::new()
+                .borders(Borders::ALL)
+                .border_style(style::Color::White),
+        };
+
+        let playlist_highlight_style = match self.playlists_section {
+            PlaylistsSection::Playlists => Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            _ => Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        };
+
+        let items = self
+            .playlists
+            .iter()
+            .map(|playlist| {
+                let mut item = Text::default();
+                let color = if playlist.id == self.open_playlist_id { Color::Blue } else { Color::White };
+                item.push_span(Span::styled(playlist.name.as_str(), Style::default().fg(color)));
+                item.push_span(Span::styled(
+                    format!(" ({})", playlist.child_count),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+                ListItem::new(item)
+            })
+            .collect::<Vec<ListItem>>();
+
+        let playlist_instructions = Title::from(Line::from(vec![
+            " New ".white().into(),
+            "<c>".blue().bold(),
+            " Rename ".white().into(),
+            "<R> ".blue().bold(),
+        ]));
+        let list = List::new(items)
+            .block(
+                playlist_block
+                    .title("Playlists")
+                    .title(playlist_instructions.alignment(Alignment::Center).position(Position::Bottom)),
+            )
+            .highlight_symbol(">>")
+            .highlight_style(playlist_highlight_style)
+            .repeat_highlight_symbol(true);
+
+        frame.render_stateful_widget(list, left, &mut self.selected_playlist);
+
+        let track_block = match self.playlists_section {
+            PlaylistsSection::Tracks => Block::new()
+                .borders(Borders::ALL)
+                .border_style(style::Color::Blue),
+            _ => Block::new()
+                .borders(Borders::ALL)
+                .border_style(style::Color::White),
+        };
+
+        let track_highlight_style = match self.playlists_section {
+            PlaylistsSection::Tracks => Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            _ => Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        };
+
+        let items = self
+            .playlist_tracks
+            .iter()
+            .enumerate()
+            .map(|(i, track)| {
+                let title = format!("{} - {}", track.name, track.album_artist);
+                let seconds = (track.run_time_ticks / 10_000_000) % 60;
+                let minutes = (track.run_time_ticks / 10_000_000 / 60) % 60;
+                let hours = (track.run_time_ticks / 10_000_000 / 60) / 60;
+                let hours_optional_text = match hours {
+                    0 => String::from(""),
+                    _ => format!("{}:", hours),
+                };
+
+                let time_span_text = format!("  {}{:02}:{:02}", hours_optional_text, minutes, seconds);
+                let index = Span::styled(
+                    format!("{}. ", i + 1),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                );
+                let color = if track.id == self.active_song_id { Color::Blue } else { Color::White };
+                let mut item: Text = Text::from(index);
+                item.push_span(Span::styled(title, Style::default().fg(color)));
+                if track.user_data.is_favorite {
+                    item.push_span(Span::styled(" ♥", Style::default().fg(Color::Red)));
+                }
+                item.push_span(Span::styled(
+                    time_span_text,
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+                ListItem::new(item)
+            })
+            .collect::<Vec<ListItem>>();
+
+        let track_instructions = Title::from(Line::from(vec![
+            " Play ".white().into(),
+            "<Enter>".blue().bold(),
+            " Remove ".white().into(),
+            "<D>".blue().bold(),
+            " Move ".white().into(),
+            "<J/K>".blue().bold(),
+            " Add to playlist ".white().into(),
+            "<P> ".blue().bold(),
+        ]));
+
+        let title = match self.playlists.iter().find(|p| p.id == self.open_playlist_id) {
+            Some(playlist) => playlist.name.clone(),
+            None => String::from("Tracks"),
+        };
+
+        if self.playlist_tracks.is_empty() {
+            let message_paragraph = Paragraph::new(if self.open_playlist_id.is_empty() {
+                "Open a playlist with <Enter>"
+            } else {
+                "This playlist is empty"
+            })
+                .block(
+                    track_block.title(title).padding(Padding::new(
+                        0, 0, center[0].height / 2, 0,
+                    )),
+                )
+                .wrap(Wrap { trim: false })
+                .alignment(Alignment::Center);
+            frame.render_widget(message_paragraph, center[0]);
+        } else {
+            let list = List::new(items)
+                .block(
+                    track_block
+                        .title(title)
+                        .title(track_instructions.alignment(Alignment::Center).position(Position::Bottom)),
+                )
+                .highlight_symbol(">>")
+                .highlight_style(track_highlight_style)
+                .scroll_padding(10)
+                .repeat_highlight_symbol(true);
+            frame.render_widget(Clear, center[0]);
+            frame.render_stateful_widget(list, center[0], &mut self.selected_playlist_track);
+        }
+
+        self.render_player(frame, center[1]);
+
+        self.render_library_right(frame, outer_layout[2]);
+    }
+}
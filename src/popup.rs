@@ -0,0 +1,197 @@
+use crate::tui::{App, TrackColumn};
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+        Paragraph
+    },
+    prelude::*,
+    widgets::*,
+};
+
+/// What the popup is asking the user for
+#[derive(Debug, PartialEq)]
+pub enum PopupAction {
+    /// Pick a playlist to add `track_ids` to
+    AddToPlaylist,
+    /// Type the name of a new playlist, which will contain `track_ids`
+    CreatePlaylist,
+    /// Type a new name for `playlist_id`
+    RenamePlaylist,
+    /// Pick a SyncPlay group to join, or leave the current one
+    SyncPlay,
+    /// Type the name of a new SyncPlay group
+    CreateSyncPlayGroup,
+    /// Toggle the optional columns of the track list
+    TrackColumns,
+}
+
+/// A modal dialog. While it is open it receives all key events
+pub struct Popup {
+    pub action: PopupAction,
+    pub input: String,
+    pub track_ids: Vec<String>,
+    pub playlist_id: String,
+    pub selected: ListState,
+}
+
+impl Popup {
+    pub fn new(action: PopupAction) -> Self {
+        let mut selected = ListState::default();
+        selected.select(Some(0));
+        Popup {
+            action,
+            input: String::from(""),
+            track_ids: vec![],
+            playlist_id: String::from(""),
+            selected,
+        }
+    }
+}
+
+impl App {
+    pub fn render_popup(&mut self, frame: &mut Frame) {
+        let area = self.centered_rect(frame.area(), 40, 40);
+        let popup = match self.popup.as_mut() {
+            Some(popup) => popup,
+            None => return,
+        };
+
+        frame.render_widget(Clear, area);
+
+        match popup.action {
+            PopupAction::AddToPlaylist => {
+                let mut items = vec![
+                    ListItem::new("+ New playlist").style(Style::default().fg(Color::Blue)),
+                ];
+                items.extend(self.playlists.iter().map(|playlist| {
+                    ListItem::new(format!("{} ({})", playlist.name, playlist.child_count))
+                }));
+
+                let instructions = Title::from(Line::from(vec![
+                    " Add ".white().into(),
+                    "<Enter>".blue().bold(),
+                    " Cancel ".white().into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(style::Color::Blue)
+                            .title(format!("Add {} track(s) to playlist", popup.track_ids.len()))
+                            .title(instructions.alignment(Alignment::Center).position(Position::Bottom)),
+                    )
+                    .highlight_symbol(">>")
+                    .highlight_style(
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .add_modifier(Modifier::REVERSED),
+                    )
+                    .repeat_highlight_symbol(true);
+                frame.render_stateful_widget(list, area, &mut popup.selected);
+            }
+            PopupAction::SyncPlay => {
+                let mut items = vec![match self.syncplay.as_ref() {
+                    Some(group) => ListItem::new(format!("- Leave {}", group.info.name)).style(Style::default().fg(Color::Blue)),
+                    None => ListItem::new("+ New group").style(Style::default().fg(Color::Blue)),
+                }];
+                items.extend(self.syncplay_groups.iter().map(|group| {
+                    let joined = self.syncplay.as_ref().is_some_and(|g| g.info.id == group.id);
+                    ListItem::new(format!("{} ({} listening)", group.name, group.participants.len()))
+                        .style(Style::default().fg(if joined { Color::Blue } else { Color::White }))
+                }));
+
+                let instructions = Title::from(Line::from(vec![
+                    " Select ".white().into(),
+                    "<Enter>".blue().bold(),
+                    " Cancel ".white().into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(style::Color::Blue)
+                            .title("SyncPlay groups")
+                            .title(instructions.alignment(Alignment::Center).position(Position::Bottom)),
+                    )
+                    .highlight_symbol(">>")
+                    .highlight_style(
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .add_modifier(Modifier::REVERSED),
+                    )
+                    .repeat_highlight_symbol(true);
+                frame.render_stateful_widget(list, area, &mut popup.selected);
+            }
+            PopupAction::TrackColumns => {
+                let items = TrackColumn::ALL.iter().map(|column| {
+                    let shown = self.track_columns.contains(column);
+                    ListItem::new(format!("[{}] {}", if shown { "x" } else { " " }, column.title()))
+                        .style(Style::default().fg(if shown { Color::Blue } else { Color::White }))
+                });
+
+                let instructions = Title::from(Line::from(vec![
+                    " Toggle ".white().into(),
+                    "<Enter>".blue().bold(),
+                    " Close ".white().into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(style::Color::Blue)
+                            .title("Track columns")
+                            .title(instructions.alignment(Alignment::Center).position(Position::Bottom)),
+                    )
+                    .highlight_symbol(">>")
+                    .highlight_style(
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .add_modifier(Modifier::REVERSED),
+                    )
+                    .repeat_highlight_symbol(true);
+                frame.render_stateful_widget(list, area, &mut popup.selected);
+            }
+            PopupAction::CreatePlaylist | PopupAction::RenamePlaylist | PopupAction::CreateSyncPlayGroup => {
+                let title = match popup.action {
+                    PopupAction::CreatePlaylist => "New playlist name",
+                    PopupAction::CreateSyncPlayGroup => "New SyncPlay group name",
+                    _ => "Rename playlist",
+                };
+                let instructions = Title::from(Line::from(vec![
+                    " Confirm ".white().into(),
+                    "<Enter>".blue().bold(),
+                    " Cancel ".white().into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                // keep the input on a single line in the middle of the popup
+                let input_area = Layout::default()
+                    .direction(Direction::Vertical)
+                    .flex(layout::Flex::Center)
+                    .constraints(vec![Constraint::Length(3)])
+                    .split(area)[0];
+                frame.render_widget(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(style::Color::Blue)
+                        .title(title)
+                        .title(instructions.alignment(Alignment::Center).position(Position::Bottom)),
+                    area,
+                );
+                frame.render_widget(
+                    Paragraph::new(format!("{}_", popup.input))
+                        .block(Block::default().borders(Borders::ALL))
+                        .wrap(Wrap { trim: false }),
+                    input_area.inner(Margin::new(2, 0)),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::tui::{App, Repeat, Song};
+
+impl App {
+
+    /// Replace the queue with an instant mix of an artist, album or song
+    pub async fn start_radio(&mut self, id: &str) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client,
+            _ => return,
+        };
+        let songs = match client.instant_mix(id).await {
+            Ok(tracks) => tracks.iter().map(|track| Song::from_track(track, client)).collect::<Vec<Song>>(),
+            Err(_) => return, // TODO: inform user of error
+        };
+        if songs.is_empty() {
+            return;
+        }
+        self.playlist = songs;
+        let _ = self.replace_playlist(); // TODO: inform user of error
+    }
+
+    /// With auto-DJ on, fetch a mix of the last song once it starts, so the queue never runs out
+    pub fn top_up_auto_dj(&mut self) {
+        if !self.auto_dj || self.repeat != Repeat::None {
+            return;
+        }
+        let last = match self.playlist.last() {
+            Some(song) if self.current_playback_state.current_index == self.playlist.len() as i64 - 1 => song,
+            _ => return,
+        };
+        if last.id == self.auto_dj_seed {
+            return;
+        }
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client.clone(),
+            _ => return,
+        };
+        self.auto_dj_seed = last.id.clone();
+        let id = last.id.clone();
+        let sender = self.auto_dj_sender.clone();
+        tokio::spawn(async move {
+            if let Ok(tracks) = client.instant_mix(&id).await {
+                let _ = sender.send(tracks);
+            }
+        });
+    }
+
+    /// Append the fetched mix, minus the songs already in the queue
+    pub fn handle_auto_dj_updates(&mut self) {
+        while let Ok(tracks) = self.auto_dj_updates.try_recv() {
+            let client = match self.client.as_ref() {
+                Some(client) => client,
+                None => continue,
+            };
+            let songs = tracks
+                .iter()
+                .filter(|track| !self.playlist.iter().any(|song| song.id == track.id))
+                .map(|track| Song::from_track(track, client))
+                .collect::<Vec<Song>>();
+            if songs.is_empty() {
+                continue;
+            }
+            let _ = self.append_to_playlist(songs); // TODO: inform user of error
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::cache;
+use crate::client::{Client, DiscographySong};
+use crate::player::MAX_VOLUME;
+use crate::syncplay::{self, SyncPlayEvent};
+use crate::tui::{App, Song};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use souvlaki::{MediaControlEvent, MediaPosition};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+/// A command from another jellyfin client, sent by the session socket to the ui
+pub enum RemoteCommand {
+    Control(MediaControlEvent), // handled like the mpris events
+    Volume(i64), // relative, in percent
+    Play { tracks: Vec<DiscographySong>, start_index: usize, play_command: String },
+    SyncPlay(SyncPlayEvent),
+    LibraryChanged(Vec<String>), // artists whose cached discography went stale
+}
+
+/// Keeps the session socket open, reconnecting every 30 seconds if the server drops it
+pub async fn t_remote(client: Client, sender: Sender<RemoteCommand>) {
+    loop {
+        if client.register_capabilities().await.is_ok() {
+            let _ = listen(&client, &sender).await;
+        }
+        tokio::time::sleep(Duration::from_secs(30)).await;
+    }
+}
+
+async fn listen(client: &Client, sender: &Sender<RemoteCommand>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (socket, _) = connect_async(client.socket_url()).await?;
+    let (mut write, mut read) = socket.split();
+
+    // the server drops sessions that stay quiet for longer than it asks for in ForceKeepAlive
+    let mut keep_alive = tokio::time::interval(Duration::from_secs(30));
+    loop {
+        tokio::select! {
+            _ = keep_alive.tick() => {
+                write.send(Message::Text(String::from("{\"MessageType\":\"KeepAlive\"}"))).await?;
+            }
+            message = read.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                };
+                let value: Value = match serde_json::from_str(&text) {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+                let data = &value["Data"];
+                let message_type = value["MessageType"].as_str().unwrap_or("");
+                let command = match message_type {
+                    "ForceKeepAlive" => {
+                        let seconds = data.as_u64().unwrap_or(60).max(2);
+                        keep_alive = tokio::time::interval(Duration::from_secs(seconds / 2));
+                        continue;
+                    }
+                    "Playstate" => playstate(data),
+                    "GeneralCommand" => general_command(data),
+                    "Play" => play(client, data).await,
+                    "LibraryChanged" => library_changed(data),
+                    "UserDataChanged" => user_data_changed(data),
+                    "SyncPlayCommand" | "SyncPlayGroupUpdate" => {
+                        syncplay::parse(client, message_type, data).await.map(RemoteCommand::SyncPlay)
+                    }
+                    _ => None,
+                };
+                if let Some(command) = command {
+                    if sender.send(command).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn playstate(data: &Value) -> Option<RemoteCommand> {
+    let event = match data["Command"].as_str()? {
+        "PlayPause" => MediaControlEvent::Toggle,
+        "Pause" => MediaControlEvent::Pause,
+        "Unpause" => MediaControlEvent::Play,
+        "NextTrack" => MediaControlEvent::Next,
+        "PreviousTrack" => MediaControlEvent::Previous,
+        "Stop" => MediaControlEvent::Stop,
+        "Seek" => {
+            // ticks are 100 nanoseconds
+            let ticks = data["SeekPositionTicks"].as_u64()?;
+            MediaControlEvent::SetPosition(MediaPosition(Duration::from_nanos(ticks * 100)))
+        }
+        _ => return None,
+    };
+    Some(RemoteCommand::Control(event))
+}
+
+fn general_command(data: &Value) -> Option<RemoteCommand> {
+    match data["Name"].as_str()? {
+        "SetVolume" => {
+            // arguments are always strings
+            let volume = data["Arguments"]["Volume"].as_str()?.parse::<f64>().ok()?;
+            Some(RemoteCommand::Control(MediaControlEvent::SetVolume(volume / 100.0)))
+        }
+        "VolumeUp" => Some(RemoteCommand::Volume(5)),
+        "VolumeDown" => Some(RemoteCommand::Volume(-5)),
+        _ => None,
+    }
+}
+
+fn ids(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|ids| ids.iter().filter_map(|id| id.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+/// New items can belong to any artist, changed and removed ones only to those that have them
+fn library_changed(data: &Value) -> Option<RemoteCommand> {
+    let marked = if !ids(&data["ItemsAdded"]).is_empty() {
+        cache::invalidate_discographies(None)
+    } else {
+        let mut changed = ids(&data["ItemsUpdated"]);
+        changed.extend(ids(&data["ItemsRemoved"]));
+        cache::invalidate_discographies(Some(&changed))
+    };
+    Some(RemoteCommand::LibraryChanged(marked))
+}
+
+/// Play counts, played state and favorites
+fn user_data_changed(data: &Value) -> Option<RemoteCommand> {
+    let changed = data["UserDataList"]
+        .as_array()?
+        .iter()
+        .filter_map(|user_data| user_data["ItemId"].as_str().map(String::from))
+        .collect::<Vec<String>>();
+    Some(RemoteCommand::LibraryChanged(cache::invalidate_discographies(Some(&changed))))
+}
+
+/// The server expands albums and playlists, so the ids are always songs
+async fn play(client: &Client, data: &Value) -> Option<RemoteCommand> {
+    let ids = data["ItemIds"]
+        .as_array()?
+        .iter()
+        .filter_map(|id| id.as_str().map(String::from))
+        .collect::<Vec<String>>();
+    let tracks = client.tracks(&ids).await.ok()?;
+    Some(RemoteCommand::Play {
+        tracks,
+        start_index: data["StartIndex"].as_u64().unwrap_or(0) as usize,
+        play_command: data["PlayCommand"].as_str().unwrap_or("PlayNow").to_string(),
+    })
+}
+
+impl App {
+
+    /// Connect the session socket, so the web ui and the apps can control playback
+    pub fn init_remote(&self) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client.clone(),
+            _ => return,
+        };
+        tokio::spawn(t_remote(client, self.remote_commands_sender.clone()));
+    }
+
+    /// Apply commands from other clients. Playback controls go through the mpris event queue
+    pub fn handle_remote_commands(&mut self) {
+        while let Ok(command) = self.remote_commands.try_recv() {
+            match command {
+                RemoteCommand::Control(event) => {
+                    let _ = self.mpris_events_sender.send(event);
+                }
+                RemoteCommand::Volume(step) => {
+                    let volume = (self.current_playback_state.volume + step).clamp(0, MAX_VOLUME);
+                    let _ = self.mpris_events_sender.send(MediaControlEvent::SetVolume(volume as f64 / 100.0));
+                }
+                RemoteCommand::SyncPlay(event) => {
+                    self.handle_syncplay_event(event);
+                }
+                RemoteCommand::LibraryChanged(artist_ids) => {
+                    if artist_ids.contains(&self.open_artist_id) {
+                        self.refresh_discography(&self.open_artist_id);
+                    }
+                }
+                RemoteCommand::Play { tracks, start_index, play_command } => {
+                    let client = match self.client.as_ref() {
+                        Some(client) => client,
+                        None => continue,
+                    };
+                    let songs = tracks
+                        .iter()
+                        .map(|track| Song::from_track(track, client))
+                        .collect::<Vec<Song>>();
+                    let _ = match play_command.as_str() {
+                        "PlayNext" => self.push_next_to_playlist(songs),
+                        "PlayLast" => self.append_to_playlist(songs),
+                        _ => {
+                            if start_index >= songs.len() {
+                                continue;
+                            }
+                            // the whole list is queued, like the controlling client shows it
+                            self.playlist = songs;
+                            self.replace_playlist_from(start_index)
+                        }
+                    }; // TODO: inform user of error
+                }
+            }
+        }
+    }
+}
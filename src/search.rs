@@ -103,14 +103,26 @@ impl App {
         let artists = self
             .search_result_artists
             .iter()
-            .map(|artist| artist.name.as_str())
-            .collect::<Vec<&str>>();
+            .map(|artist| {
+                let mut item = Text::from(artist.name.as_str());
+                if artist.user_data.is_favorite {
+                    item.push_span(Span::styled(" ♥", Style::default().fg(Color::Red)));
+                }
+                ListItem::new(item)
+            })
+            .collect::<Vec<ListItem>>();
 
         let albums = self
             .search_result_albums
             .iter()
-            .map(|album| album.name.as_str())
-            .collect::<Vec<&str>>();
+            .map(|album| {
+                let mut item = Text::from(album.name.as_str());
+                if album.user_data.is_favorite {
+                    item.push_span(Span::styled(" ♥", Style::default().fg(Color::Red)));
+                }
+                ListItem::new(item)
+            })
+            .collect::<Vec<ListItem>>();
         let tracks = self
             .search_result_tracks
             .iter()
@@ -129,8 +141,14 @@ impl App {
                 if track.has_lyrics{
                     time_span_text.push_str(" (l)");
                 }
+                let favorite = if track.user_data.is_favorite {
+                    Span::styled(" ♥", Style::default().fg(Color::Red))
+                } else {
+                    Span::from("")
+                };
                 if track.id == self.active_song_id {
                     let mut time: Text = Text::from(title);
+                    time.push_span(favorite);
                     time.push_span(
                         Span::styled(
                             time_span_text,
@@ -141,6 +159,7 @@ impl App {
                         .style(Style::default().fg(Color::Blue))
                 } else {
                     let mut time: Text = Text::from(title);
+                    time.push_span(favorite);
                     time.push_span(
                         Span::styled(
                             time_span_text,
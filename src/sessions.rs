@@ -0,0 +1,194 @@
+use crate::client::Session;
+use crate::player::PlayerCommand;
+use crate::tui::App;
+
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    Frame,
+    widgets::{
+        Block,
+        block::Title,
+        block::Position,
+        Borders,
+        Paragraph
+    },
+    prelude::*,
+    widgets::*,
+};
+
+fn format_ticks(ticks: u64) -> String {
+    let seconds = ticks / 10_000_000;
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, (seconds / 60) % 60, seconds % 60),
+    }
+}
+
+impl App {
+
+    /// Fetch the other sessions in the background, at most every 3 seconds while the Sessions tab is open
+    pub fn refresh_sessions(&mut self, force: bool) {
+        if !force && self.last_sessions_refresh.elapsed() < Duration::from_secs(3) {
+            return;
+        }
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client.clone(),
+            _ => return,
+        };
+        self.last_sessions_refresh = Instant::now();
+        let sender = self.sessions_sender.clone();
+        tokio::spawn(async move {
+            if let Ok(sessions) = client.sessions().await {
+                let _ = sender.send(sessions);
+            }
+        });
+    }
+
+    /// Swap in the refreshed sessions, keeping the cursor on the same one
+    pub fn handle_sessions_updates(&mut self) {
+        while let Ok(sessions) = self.sessions_updates.try_recv() {
+            let selected_id = self.selected_session().map(|session| session.id.clone());
+            self.sessions = sessions;
+            let index = self
+                .sessions
+                .iter()
+                .position(|session| Some(&session.id) == selected_id.as_ref());
+            self.selected_session.select(match index {
+                Some(index) => Some(index),
+                None if self.sessions.is_empty() => None,
+                None => Some(0),
+            });
+        }
+    }
+
+    pub fn selected_session(&self) -> Option<&Session> {
+        self.sessions.get(self.selected_session.selected()?)
+    }
+
+    /// Hand the queue over to the selected session, at the current song and position, and pause here
+    pub async fn transfer_queue(&mut self) {
+        let (client, session) = match (self.client.as_ref(), self.selected_session()) {
+            (Some(client), Some(session)) => (client, session),
+            _ => return,
+        };
+        if self.playlist.is_empty() {
+            return;
+        }
+        let song_ids = self.playlist.iter().map(|song| song.id.clone()).collect::<Vec<String>>();
+        let start_index = self.current_playback_state.current_index.max(0) as usize;
+        let position_ticks = (self.current_playback_state.duration * self.current_playback_state.percentage * 100000.0) as u64;
+        if client.session_play(&session.id, &song_ids, start_index, position_ticks).await.is_ok() {
+            self.player.send(PlayerCommand::SetProperty("pause", true.into()));
+            self.paused = true;
+        }
+        self.refresh_sessions(true);
+    }
+
+    pub fn render_sessions(&mut self, app_container: Rect, frame: &mut Frame) {
+        let outer_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(76),
+                Constraint::Percentage(24),
+            ])
+            .split(app_container);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(86), Constraint::Min(8)])
+            .split(outer_layout[0]);
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(style::Color::Blue)
+            .title("Sessions");
+
+        if self.sessions.is_empty() {
+            let message = match self.client.as_ref() {
+                Some(client) if client.offline => "Other sessions cannot be controlled while offline",
+                _ => "No other sessions to control. Jellyfin clients show up here while they are open",
+            };
+            let message_paragraph = Paragraph::new(message)
+                .block(block.padding(Padding::new(0, 0, left[0].height / 2, 0)))
+                .wrap(Wrap { trim: false })
+                .alignment(Alignment::Center);
+            frame.render_widget(message_paragraph, left[0]);
+        } else {
+            let items = self
+                .sessions
+                .iter()
+                .map(|session| {
+                    let mut item = Text::default();
+                    item.push_span(Span::styled(
+                        format!("{} - {}", session.device_name, session.client),
+                        Style::default().fg(Color::White),
+                    ));
+                    item.push_span(Span::styled(
+                        format!("  {}", session.user_name),
+                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                    ));
+                    if let Some(volume) = session.play_state.volume_level {
+                        item.push_span(Span::styled(
+                            format!("  Volume: {}%", volume),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    let now_playing = match &session.now_playing_item {
+                        Some(song) => Line::from(vec![
+                            Span::styled(
+                                if session.play_state.is_paused { "   Paused: " } else { "   Playing: " },
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                            Span::styled(
+                                format!("{} - {}", song.name, song.artists.join(", ")),
+                                Style::default().fg(Color::Blue),
+                            ),
+                            Span::styled(
+                                format!(
+                                    "  {} / {}",
+                                    format_ticks(session.play_state.position_ticks),
+                                    format_ticks(song.run_time_ticks)
+                                ),
+                                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                            ),
+                        ]),
+                        None => Line::styled("   Nothing playing", Style::default().fg(Color::DarkGray)),
+                    };
+                    item.push_line(now_playing);
+                    ListItem::new(item)
+                })
+                .collect::<Vec<ListItem>>();
+
+            let instructions = Title::from(Line::from(vec![
+                " Send queue ".white().into(),
+                "<Enter>".blue().bold(),
+                " Play/Pause ".white().into(),
+                "<Space>".blue().bold(),
+                " Next/Previous ".white().into(),
+                "<n/N>".blue().bold(),
+                " Seek ".white().into(),
+                "<r/s>".blue().bold(),
+                " Volume ".white().into(),
+                "<+/->".blue().bold(),
+                " Stop ".white().into(),
+                "<S> ".blue().bold(),
+            ]));
+            let list = List::new(items)
+                .block(block.title(instructions.alignment(Alignment::Center).position(Position::Bottom)))
+                .highlight_symbol(">>")
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::White)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .repeat_highlight_symbol(true);
+            frame.render_stateful_widget(list, left[0], &mut self.selected_session);
+        }
+
+        self.render_player(frame, left[1]);
+
+        self.render_library_right(frame, outer_layout[1]);
+    }
+}
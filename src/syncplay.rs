@@ -0,0 +1,322 @@
+use crate::client::{authorization, Client, DiscographySong};
+use crate::player::PlayerCommand;
+use crate::tui::{App, MpvPlaybackState, Song};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A SyncPlay group as listed by the server
+#[derive(Debug, Deserialize, Clone)]
+pub struct GroupInfo {
+    #[serde(rename = "GroupId", default)]
+    pub id: String,
+    #[serde(rename = "GroupName", default)]
+    pub name: String,
+    #[serde(rename = "State", default)]
+    pub state: String,
+    #[serde(rename = "Participants", default)]
+    pub participants: Vec<String>,
+}
+
+/// The group we are in. `playlist_item_ids` runs parallel to `App::playlist`,
+/// since the group tells songs apart by their entry in its playlist
+pub struct SyncPlayGroup {
+    pub info: GroupInfo,
+    pub playlist_item_ids: Vec<String>,
+}
+
+/// A group update or command, sent by the session socket to the ui
+pub enum SyncPlayEvent {
+    Joined(GroupInfo),
+    Left,
+    UserJoined(String),
+    UserLeft(String),
+    State(String),
+    PlayQueue {
+        tracks: Vec<DiscographySong>,
+        playlist_item_ids: Vec<String>,
+        playing_index: usize,
+        start_position_ticks: u64,
+        is_playing: bool,
+    },
+    Command { command: String, position_ticks: u64, when: String },
+}
+
+/// Reads a SyncPlayGroupUpdate or SyncPlayCommand message of the session socket
+pub async fn parse(client: &Client, message_type: &str, data: &Value) -> Option<SyncPlayEvent> {
+    if message_type == "SyncPlayCommand" {
+        return Some(SyncPlayEvent::Command {
+            command: data["Command"].as_str()?.to_string(),
+            position_ticks: data["PositionTicks"].as_u64().unwrap_or(0),
+            when: data["When"].as_str().unwrap_or("").to_string(),
+        });
+    }
+    let update = &data["Data"];
+    match data["Type"].as_str()? {
+        "GroupJoined" => serde_json::from_value(update.clone()).ok().map(SyncPlayEvent::Joined),
+        "GroupLeft" | "NotInGroup" | "GroupDoesNotExist" | "LibraryAccessDenied" => Some(SyncPlayEvent::Left),
+        "UserJoined" => Some(SyncPlayEvent::UserJoined(update.as_str()?.to_string())),
+        "UserLeft" => Some(SyncPlayEvent::UserLeft(update.as_str()?.to_string())),
+        "StateUpdate" => Some(SyncPlayEvent::State(update["State"].as_str()?.to_string())),
+        "PlayQueue" => {
+            let playlist = update["Playlist"].as_array()?;
+            let ids = playlist
+                .iter()
+                .filter_map(|item| item["ItemId"].as_str().map(String::from))
+                .collect::<Vec<String>>();
+            let tracks = client.tracks(&ids).await.ok()?;
+            // a song the server would not give us would shift every index after it
+            if tracks.len() != ids.len() {
+                return None;
+            }
+            Some(SyncPlayEvent::PlayQueue {
+                tracks,
+                playlist_item_ids: playlist
+                    .iter()
+                    .filter_map(|item| item["PlaylistItemId"].as_str().map(String::from))
+                    .collect(),
+                playing_index: update["PlayingItemIndex"].as_i64().unwrap_or(0).max(0) as usize,
+                start_position_ticks: update["StartPositionTicks"].as_u64().unwrap_or(0),
+                is_playing: update["IsPlaying"].as_bool().unwrap_or(false),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Produces the groups on the server
+///
+pub async fn groups(client: &Client) -> Result<Vec<GroupInfo>, reqwest::Error> {
+    let url = format!("{}/SyncPlay/List", client.base_url);
+    reqwest::Client::new()
+        .get(url)
+        .header("X-MediaBrowser-Token", client.access_token.to_string())
+        .header("x-emby-authorization", authorization(&client.device_id))
+        .send()
+        .await?
+        .json()
+        .await
+}
+
+/// Sends a request to /SyncPlay/{endpoint}. The server answers through the session socket
+///
+pub async fn request(base_url: String, access_token: String, device_id: String, endpoint: String, body: Value) -> Result<(), reqwest::Error> {
+    let url = format!("{}/SyncPlay/{}", base_url, endpoint);
+    // new http client, like report_progress, so this can be spawned
+    reqwest::Client::new()
+        .post(url)
+        .header("X-MediaBrowser-Token", access_token)
+        .header("x-emby-authorization", authorization(&device_id))
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+impl App {
+
+    /// Send a request without waiting for it. Returns false when we are not in a group,
+    /// so the caller goes on to control the local player
+    fn syncplay_request(&self, endpoint: &str, body: Value) -> bool {
+        if self.syncplay.is_none() {
+            return false;
+        }
+        if let Some(client) = self.client.as_ref() {
+            tokio::spawn(request(client.base_url.clone(), client.access_token.clone(), client.device_id.clone(), endpoint.to_string(), body));
+        }
+        true
+    }
+
+    pub fn create_syncplay_group(&self, name: &str) {
+        if let Some(client) = self.client.as_ref() {
+            let body = serde_json::json!({ "GroupName": name });
+            tokio::spawn(request(client.base_url.clone(), client.access_token.clone(), client.device_id.clone(), String::from("New"), body));
+        }
+    }
+
+    pub fn join_syncplay_group(&self, group_id: &str) {
+        if let Some(client) = self.client.as_ref() {
+            let body = serde_json::json!({ "GroupId": group_id });
+            tokio::spawn(request(client.base_url.clone(), client.access_token.clone(), client.device_id.clone(), String::from("Join"), body));
+        }
+    }
+
+    pub fn leave_syncplay_group(&self) {
+        self.syncplay_request("Leave", serde_json::json!({}));
+    }
+
+    /// The playlist entry of a queue index, as the group knows it
+    fn playlist_item_id(&self, index: usize) -> String {
+        self.syncplay
+            .as_ref()
+            .and_then(|group| group.playlist_item_ids.get(index).cloned())
+            .unwrap_or_default()
+    }
+
+    pub fn syncplay_pause(&self, pause: bool) -> bool {
+        self.syncplay_request(if pause { "Pause" } else { "Unpause" }, serde_json::json!({}))
+    }
+
+    /// Seek the group to `seconds` into the current song
+    pub fn syncplay_seek(&self, seconds: f64) -> bool {
+        let ticks = (seconds.max(0.0) * 10_000_000.0) as u64;
+        self.syncplay_request("Seek", serde_json::json!({ "PositionTicks": ticks }))
+    }
+
+    pub fn syncplay_skip(&self, next: bool) -> bool {
+        let index = self.current_playback_state.current_index.max(0) as usize;
+        let body = serde_json::json!({ "PlaylistItemId": self.playlist_item_id(index) });
+        self.syncplay_request(if next { "NextItem" } else { "PreviousItem" }, body)
+    }
+
+    /// Make `App::playlist` the group's playlist, playing from the song at `start`
+    pub fn syncplay_set_queue(&self, start: usize) -> bool {
+        let ids = self.playlist.iter().map(|song| song.id.clone()).collect::<Vec<String>>();
+        self.syncplay_request("SetNewQueue", serde_json::json!({
+            "PlayingQueue": ids,
+            "PlayingItemPosition": start,
+            "StartPositionTicks": 0,
+        }))
+    }
+
+    pub fn syncplay_enqueue(&self, songs: &[Song], next: bool) -> bool {
+        let ids = songs.iter().map(|song| song.id.clone()).collect::<Vec<String>>();
+        self.syncplay_request("Queue", serde_json::json!({
+            "ItemIds": ids,
+            "Mode": if next { "QueueNext" } else { "Queue" },
+        }))
+    }
+
+    pub fn syncplay_jump(&self, index: usize) -> bool {
+        self.syncplay_request("SetPlaylistItem", serde_json::json!({ "PlaylistItemId": self.playlist_item_id(index) }))
+    }
+
+    pub fn syncplay_remove(&self, indices: std::ops::Range<usize>) -> bool {
+        let ids = indices.map(|index| self.playlist_item_id(index)).collect::<Vec<String>>();
+        self.syncplay_request("RemoveFromPlaylist", serde_json::json!({ "PlaylistItemIds": ids }))
+    }
+
+    pub fn syncplay_move(&self, from: usize, to: usize) -> bool {
+        self.syncplay_request("MovePlaylistItem", serde_json::json!({
+            "PlaylistItemId": self.playlist_item_id(from),
+            "NewIndex": to,
+        }))
+    }
+
+    /// Tell the group we are at `position_ticks` and ready to play, so it can start everyone together
+    fn syncplay_ready(&self, position_ticks: u64, is_playing: bool) {
+        let index = self.current_playback_state.current_index.max(0) as usize;
+        self.syncplay_request("Ready", serde_json::json!({
+            "When": Utc::now().to_rfc3339(),
+            "PositionTicks": position_ticks,
+            "IsPlaying": is_playing,
+            "PlaylistItemId": self.playlist_item_id(index),
+        }));
+    }
+
+    pub fn handle_syncplay_event(&mut self, event: SyncPlayEvent) {
+        match event {
+            SyncPlayEvent::Joined(info) => {
+                self.syncplay = Some(SyncPlayGroup { info, playlist_item_ids: vec![] });
+            }
+            SyncPlayEvent::Left => {
+                self.syncplay = None;
+            }
+            SyncPlayEvent::UserJoined(user) => {
+                if let Some(group) = self.syncplay.as_mut() {
+                    group.info.participants.push(user);
+                }
+            }
+            SyncPlayEvent::UserLeft(user) => {
+                if let Some(group) = self.syncplay.as_mut() {
+                    if let Some(index) = group.info.participants.iter().position(|p| *p == user) {
+                        group.info.participants.remove(index);
+                    }
+                }
+            }
+            SyncPlayEvent::State(state) => {
+                if let Some(group) = self.syncplay.as_mut() {
+                    group.info.state = state;
+                }
+            }
+            SyncPlayEvent::PlayQueue { tracks, playlist_item_ids, playing_index, start_position_ticks, is_playing } => {
+                let client = match self.client.as_ref() {
+                    Some(client) => client,
+                    None => return,
+                };
+                let songs = tracks.iter().map(|track| Song::from_track(track, client)).collect::<Vec<Song>>();
+                let current = self.current_playback_state.current_index.max(0) as usize;
+                let same_song = !self.playlist_item_id(current).is_empty()
+                    && playlist_item_ids.get(playing_index) == Some(&self.playlist_item_id(current));
+                if let Some(group) = self.syncplay.as_mut() {
+                    group.playlist_item_ids = playlist_item_ids;
+                }
+
+                // the queue changed around the current song, which keeps playing
+                if same_song && self.replace_queue_around_current(songs.clone(), playing_index).is_ok() {
+                    return;
+                }
+
+                if songs.is_empty() {
+                    self.player.send(PlayerCommand::Stop);
+                    self.playlist = songs;
+                    return;
+                }
+                let percentage = match tracks.get(playing_index) {
+                    Some(track) if track.run_time_ticks > 0 => start_position_ticks as f64 / track.run_time_ticks as f64 * 100.0,
+                    _ => 0.0,
+                };
+                self.playlist = songs;
+                let state = MpvPlaybackState {
+                    percentage,
+                    duration: 0.0,
+                    current_index: playing_index as i64,
+                    volume: self.current_playback_state.volume,
+                    commands: 0,
+                };
+                // everyone loads paused, the group unpauses once all of us are ready
+                if self.start_playlist(state, true).is_ok() {
+                    self.current_playback_state.current_index = playing_index as i64;
+                    self.syncplay_ready(start_position_ticks, is_playing);
+                }
+            }
+            SyncPlayEvent::Command { command, position_ticks, when } => {
+                let position = position_ticks as f64 / 10_000_000.0;
+                match command.as_str() {
+                    "Unpause" => {
+                        // catch up with the others if the command was meant for a moment that already passed.
+                        // without time sync, this trusts the server and local clocks to agree
+                        let late = DateTime::parse_from_rfc3339(&when)
+                            .map(|when| (Utc::now() - when.with_timezone(&Utc)).num_milliseconds().max(0) as f64 / 1000.0)
+                            .unwrap_or(0.0);
+                        self.player.send(PlayerCommand::Seek(position + late));
+                        self.player.send(PlayerCommand::SetProperty("pause", false.into()));
+                        self.paused = false;
+                    }
+                    "Pause" => {
+                        self.player.send(PlayerCommand::SetProperty("pause", true.into()));
+                        self.player.send(PlayerCommand::Seek(position));
+                        self.paused = true;
+                    }
+                    "Seek" => {
+                        let was_playing = !self.paused;
+                        self.player.send(PlayerCommand::SetProperty("pause", true.into()));
+                        self.player.send(PlayerCommand::Seek(position));
+                        self.paused = true;
+                        self.syncplay_ready(position_ticks, was_playing);
+                    }
+                    "Stop" => {
+                        self.player.send(PlayerCommand::SetProperty("pause", true.into()));
+                        self.player.send(PlayerCommand::Seek(0.0));
+                        self.paused = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
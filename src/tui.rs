@@ -1,16 +1,23 @@
-use crate::client::{self, report_progress, Album, Artist, Client, DiscographySong, ProgressReport, Lyric};
+use crate::client::{self, report_progress, Album, Artist, Client, Discography, DiscographySong, Genre, Playlist, ProgressReport, Lyric, Session};
 use crate::keyboard::{*};
 use crate::mpris;
-
-use libmpv::{*};
+use crate::popup::Popup;
+use crate::downloads::{self, Download, DownloadEvent};
+use crate::cache;
+use crate::remote::RemoteCommand;
+use crate::syncplay::{GroupInfo, SyncPlayGroup};
+use crate::albums::AlbumSort;
+use crate::player::{Player, PlayerCommand, PlayerFile};
 
 use std::io::Stdout;
 
-use souvlaki::{MediaControlEvent, MediaControls};
+use souvlaki::{MediaControlEvent, MediaControls, MediaPlayback};
 
 use dirs::cache_dir;
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
+
 use ratatui::{
     Terminal,
     Frame,
@@ -20,52 +27,232 @@ use ratatui::{
 
 use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
 
 /// A type alias for the terminal type used in this application
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::{Arc, Mutex};
-
-use std::thread;
 
+#[derive(Clone)]
 pub struct MpvPlaybackState {
     pub percentage: f64,
     pub duration: f64,
     pub current_index: i64,
-    pub last_index: i64,
+    pub commands: u64, // player commands handled when this was sent
     pub volume: i64,
 }
 
+/// Repeat modes, mapped to mpv's `loop-playlist` and `loop-file`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Repeat {
+    #[default]
+    None,
+    All,
+    One,
+}
+
+impl Repeat {
+    /// Off -> all -> one -> off
+    pub fn next(self) -> Self {
+        match self {
+            Repeat::None => Repeat::All,
+            Repeat::All => Repeat::One,
+            Repeat::One => Repeat::None,
+        }
+    }
+
+    /// The name jellyfin uses in progress reports
+    pub fn as_jellyfin_str(&self) -> &'static str {
+        match self {
+            Repeat::None => "RepeatNone",
+            Repeat::All => "RepeatAll",
+            Repeat::One => "RepeatOne",
+        }
+    }
+}
+
+/// A row of the Library track list, pointing into `App::tracks`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrackRow {
+    Album(usize), // header of the album that starts with this song
+    Song(usize),
+}
+
+/// Optional columns of the Library track list, besides the index, title and length
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrackColumn {
+    PlayCount,
+    Played,
+    Favorite,
+    LastPlayed,
+    Genre,
+    Codec,
+}
+
+impl TrackColumn {
+    /// In the order they are shown
+    pub const ALL: [TrackColumn; 6] = [
+        TrackColumn::PlayCount,
+        TrackColumn::Played,
+        TrackColumn::Favorite,
+        TrackColumn::LastPlayed,
+        TrackColumn::Genre,
+        TrackColumn::Codec,
+    ];
+
+    /// The name used in the `track_columns` list of the config file
+    pub fn from_config(name: &str) -> Option<Self> {
+        match name {
+            "play_count" => Some(TrackColumn::PlayCount),
+            "played" => Some(TrackColumn::Played),
+            "favorite" => Some(TrackColumn::Favorite),
+            "last_played" => Some(TrackColumn::LastPlayed),
+            "genre" => Some(TrackColumn::Genre),
+            "codec" => Some(TrackColumn::Codec),
+            _ => None,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            TrackColumn::PlayCount => "Plays",
+            TrackColumn::Played => "Played",
+            TrackColumn::Favorite => "♥",
+            TrackColumn::LastPlayed => "Last played",
+            TrackColumn::Genre => "Genre",
+            TrackColumn::Codec => "Codec",
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        match self {
+            TrackColumn::PlayCount => 5,
+            TrackColumn::Played => 6,
+            TrackColumn::Favorite => 1,
+            TrackColumn::LastPlayed => 11,
+            TrackColumn::Genre => 16,
+            TrackColumn::Codec => 6,
+        }
+    }
+
+    /// The cell of this column for a track
+    pub fn value(&self, track: &DiscographySong) -> String {
+        match self {
+            TrackColumn::PlayCount => track.user_data.play_count.to_string(),
+            TrackColumn::Played => String::from(if track.user_data.played { "✓" } else { "" }),
+            TrackColumn::Favorite => String::from(if track.user_data.is_favorite { "♥" } else { "" }),
+            // the date part of the ISO timestamp
+            TrackColumn::LastPlayed => match &track.user_data.last_played_date {
+                Some(date) => date.chars().take(10).collect(),
+                None => String::from("never"),
+            },
+            TrackColumn::Genre => track.genres.first().cloned().unwrap_or_default(),
+            TrackColumn::Codec => {
+                let source = match track.media_sources.first() {
+                    Some(source) => source,
+                    None => return String::from(""),
+                };
+                match source.media_streams.iter().find(|stream| stream.type_ == "Audio") {
+                    Some(stream) => stream.codec.clone(),
+                    None => source.container.clone(),
+                }
+            }
+        }
+    }
+}
+
+/// Fresh data from the background refreshes of the metadata cache
+pub enum LibraryUpdate {
+    Artists(Vec<Artist>),
+    Discography(String, Discography), // artist id
+}
+
+/// The queue as saved to the cache dir, restored on the next launch
+#[derive(Serialize, Deserialize)]
+struct SavedQueue {
+    playlist: Vec<Song>,
+    current_index: i64,
+    percentage: f64,
+    volume: i64,
+}
+
 /// Internal song representation. Used in the queue and passed to MPV
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Song {
     pub id: String,
+    #[serde(skip)] // contains the access token, rebuilt from the id on restore
     pub url: String,
     pub name: String,
     pub artist: String,
     pub artist_items: Vec<Artist>,
     pub album: String,
     pub parent_id: String,
+    #[serde(default)]
+    pub album_image_tag: String, // the album's primary image tag, keys the cover art cache
     pub production_year: u64,
+    #[serde(default)]
+    pub index_number: u64, // track number
+    #[serde(default)]
+    pub run_time_ticks: u64,
+    #[serde(default)]
+    pub is_favorite: bool,
+    #[serde(default)]
+    pub normalization_gain: Option<f64>, // dB, computed by the server
+    #[serde(default)]
+    pub album_normalization_gain: Option<f64>,
+}
+
+impl Song {
+    /// Builds a queue entry from a track as the server returns it
+    pub fn from_track(track: &DiscographySong, client: &Client) -> Self {
+        Song {
+            id: track.id.clone(),
+            url: client.song_url_sync(track.id.clone()),
+            name: track.name.clone(),
+            artist: track.album_artist.clone(),
+            artist_items: track.artist_items.clone(),
+            album: track.album.clone(),
+            parent_id: track.parent_id.clone(),
+            album_image_tag: track.album_primary_image_tag.clone(),
+            production_year: track.production_year,
+            index_number: track.index_number,
+            run_time_ticks: track.run_time_ticks,
+            is_favorite: track.user_data.is_favorite,
+            normalization_gain: track.normalization_gain,
+            album_normalization_gain: track.album_normalization_gain,
+        }
+    }
 }
 
 pub struct App {
     pub exit: bool,
 
     pub artists: Vec<Artist>, // all artists
-    pub tracks: Vec<DiscographySong>, // current artist's tracks
+    pub tracks: Vec<DiscographySong>, // current artist's tracks, grouped by album
+    pub open_artist_id: String, // whose tracks are shown
+    pub favorite_album_ids: Vec<String>, // of the current artist
+    pub collapsed_albums: Vec<String>, // album ids folded in the track list
     pub lyrics: Option<(String, Vec<Lyric>, bool)>, // ID, lyrics, time_synced
     pub playlist: Vec<Song>, // (URL, Title, Artist, Album)
     pub active_song_id: String,
 
     pub metadata: Option<client::MediaStream>,
     pub cover_art: Option<Box<dyn StatefulProtocol>>,
-    cover_art_dir: String,
+    cover_art_key: (String, String), // album id and image tag of the decoded cover, kept within an album
+    pub mpris_song_id: String, // whose metadata MPRIS shows
+    pub mpris_playback: Option<MediaPlayback>, // last status and position sent to MPRIS
+    pub mpris_position: (f64, Instant), // position at the last frame, to tell seeks from playback
+    pub mpris_paused: bool, // pause state at the last frame
+    pub mpris_stopped: bool, // stopped through MPRIS, which is not the same as paused
+    pub mpris_volume: Option<i64>, // last volume sent to MPRIS
     picker: Option<Picker>,
 
     pub paused: bool,
+    pub shuffle: bool,
+    pub repeat: Repeat,
     pub buffering: i8, // 0 = not buffering, 1 = requested to buffer, 2 = buffering
 
     pub spinner: usize, // spinner for buffering
@@ -79,7 +266,10 @@ pub struct App {
     // Search - active section (Artists, Albums, Tracks)
     pub search_section: SearchSection, // current active section (Artists, Albums, Tracks)
 
-    // active tab (Music, Search)
+    // Playlists - active section (Playlists, Tracks)
+    pub playlists_section: PlaylistsSection,
+
+    // active tab (Music, Search, Playlists)
     pub active_tab: ActiveTab,
     pub searching: bool,
     pub search_term: String,
@@ -92,9 +282,21 @@ pub struct App {
     pub search_result_albums: Vec<Album>,
     pub search_result_tracks: Vec<DiscographySong>,
 
+    pub playlists: Vec<Playlist>, // the user's jellyfin playlists
+    pub playlist_tracks: Vec<DiscographySong>, // songs of the opened playlist
+    pub open_playlist_id: String,
+
+    pub popup: Option<Popup>, // modal dialog drawn over everything else
+
+    pub downloads: Vec<Download>, // tracks in the offline cache, finished or not
+    pub download_sender: Option<tokio::sync::mpsc::UnboundedSender<String>>, // ids for the download worker
+    pub download_events_sender: Sender<DownloadEvent>,
+    pub download_events: Receiver<DownloadEvent>, // progress from the download worker
+
     // ratatui list indexes
     pub selected_artist: ListState,
-    pub selected_track: ListState,
+    pub selected_track: TableState,
+    pub track_columns: Vec<TrackColumn>, // shown in the track list, toggled with C
     pub selected_queue_item: ListState,
     pub selected_lyric: ListState,
     pub selected_lyric_manual_override: bool,
@@ -103,19 +305,89 @@ pub struct App {
     pub selected_search_album: ListState,
     pub selected_search_track: ListState,
 
+    pub selected_playlist: ListState,
+    pub selected_playlist_track: ListState,
+
+    pub selected_download: ListState,
+
+    pub selected_session: ListState,
+
+    pub favorite_tracks: Vec<DiscographySong>, // shown in the Favorites tab
+    pub selected_favorite: ListState,
+
+    // Dashboard - active section and its lists
+    pub dashboard_section: DashboardSection,
+    pub recently_added_albums: Vec<Album>,
+    pub recently_played_tracks: Vec<DiscographySong>,
+    pub most_played_tracks: Vec<DiscographySong>,
+    pub favorite_albums: Vec<Album>,
+    pub selected_recently_added: ListState,
+    pub selected_recently_played: ListState,
+    pub selected_most_played: ListState,
+    pub selected_favorite_album: ListState,
+
+    // Genres - active section, the genres and what the open one contains
+    pub genres_section: GenresSection,
+    pub genres: Vec<Genre>,
+    pub genre_artists: Vec<Artist>,
+    pub genre_albums: Vec<Album>,
+    pub open_genre_id: String,
+    pub selected_genre: ListState,
+    pub selected_genre_artist: ListState,
+    pub selected_genre_album: ListState,
+
+    // Albums - active section, every album and the songs of the open one
+    pub albums_section: AlbumsSection,
+    pub albums: Vec<Album>,
+    pub album_sort: AlbumSort,
+    pub album_tracks: Vec<DiscographySong>,
+    pub open_album_id: String,
+    pub selected_album: ListState,
+    pub selected_album_track: ListState,
+
     pub client: Option<Client>, // jellyfin http client
 
-    // mpv is run in a separate thread, this is the handle
-    mpv_thread: Option<thread::JoinHandle<()>>,
-    pub mpv_state: Arc<Mutex<MpvState>>, // shared mutex for controlling mpv
+    // mpv runs on the player thread for the whole session, we only send it commands
+    pub player: Player,
 
-    // every second, we get the playback state from the mpv thread
-    sender: Sender<MpvPlaybackState>,
+    // the player sends its state whenever something changes
     receiver: Receiver<MpvPlaybackState>,
     pub current_playback_state: MpvPlaybackState,
     old_percentage: f64,
     scrobble_this: (String, u64), // an id of the previous song we want to scrobble when it ends
+    playing_unreported: bool, // the current song started paused, e.g. a restored queue, and is reported once it plays
     pub controls: Option<MediaControls>,
+
+    // offline, we periodically try to reach the server again in the background
+    last_reconnect: Instant,
+    reconnect_sender: Sender<Client>,
+    reconnect_receiver: Receiver<Client>,
+
+    library_updates_sender: Sender<LibraryUpdate>,
+    library_updates: Receiver<LibraryUpdate>,
+
+    // commands from other jellyfin clients, through the session socket
+    pub remote_commands_sender: Sender<RemoteCommand>,
+    pub remote_commands: Receiver<RemoteCommand>,
+
+    // media keys and mpris controllers, and the playback controls of remote commands
+    pub mpris_events_sender: Sender<MediaControlEvent>,
+    pub mpris_events: Receiver<MediaControlEvent>,
+
+    // other devices, polled while the Sessions tab is open
+    pub sessions: Vec<Session>,
+    pub last_sessions_refresh: Instant,
+    pub sessions_sender: Sender<Vec<Session>>,
+    pub sessions_updates: Receiver<Vec<Session>>,
+
+    pub syncplay: Option<SyncPlayGroup>, // the SyncPlay group we are listening with
+    pub syncplay_groups: Vec<GroupInfo>, // groups we can join
+
+    // auto-DJ, the queue is topped up with an instant mix of the last song
+    pub auto_dj: bool,
+    pub auto_dj_seed: String, // id of the song the last mix was fetched for
+    pub auto_dj_sender: Sender<Vec<DiscographySong>>,
+    pub auto_dj_updates: Receiver<Vec<DiscographySong>>,
 }
 
 impl Default for App {
@@ -132,6 +404,13 @@ impl Default for App {
         picker.guess_protocol();
 
         let (sender, receiver) = channel();
+        let (download_events_sender, download_events) = channel();
+        let (reconnect_sender, reconnect_receiver) = channel();
+        let (library_updates_sender, library_updates) = channel();
+        let (remote_commands_sender, remote_commands) = channel();
+        let (mpris_events_sender, mpris_events) = channel();
+        let (sessions_sender, sessions_updates) = channel();
+        let (auto_dj_sender, auto_dj_updates) = channel();
 
         let controls = match mpris::mpris() {
             Ok(controls) => Some(controls),
@@ -142,17 +421,25 @@ impl Default for App {
             exit: false,
             artists: vec![],
             tracks: vec![],
+            open_artist_id: String::from(""),
+            favorite_album_ids: vec![],
+            collapsed_albums: vec![],
             lyrics: None,
             metadata: None,
             playlist: vec![],
             active_song_id: String::from(""),
             cover_art: None,
-            cover_art_dir: match cache_dir() {
-                Some(dir) => dir,
-                None => PathBuf::from("./"),
-            }.join("jellyfin-tui").join("covers").to_str().unwrap_or("").to_string(),
+            cover_art_key: (String::from(""), String::from("")),
+            mpris_song_id: String::from(""),
+            mpris_playback: None,
+            mpris_position: (0.0, Instant::now()),
+            mpris_paused: false,
+            mpris_stopped: false,
+            mpris_volume: None,
             picker: Some(picker),
             paused: true,
+            shuffle: false,
+            repeat: Repeat::default(),
 
             buffering: 0,
             spinner: 0,
@@ -166,6 +453,8 @@ impl Default for App {
 
             search_section: SearchSection::default(),
 
+            playlists_section: PlaylistsSection::default(),
+
             active_tab: ActiveTab::default(),
             searching: false,
             search_term: String::from(""),
@@ -178,8 +467,20 @@ impl Default for App {
             search_result_albums: vec![],
             search_result_tracks: vec![],
 
+            playlists: vec![],
+            playlist_tracks: vec![],
+            open_playlist_id: String::from(""),
+
+            popup: None,
+
+            downloads: vec![],
+            download_sender: None,
+            download_events_sender,
+            download_events,
+
             selected_artist: ListState::default(),
-            selected_track: ListState::default(),
+            selected_track: TableState::default(),
+            track_columns: vec![TrackColumn::Favorite],
             selected_queue_item: ListState::default(),
             selected_lyric: ListState::default(),
             selected_lyric_manual_override: false,
@@ -187,48 +488,84 @@ impl Default for App {
             selected_search_artist: ListState::default(),
             selected_search_album: ListState::default(),
             selected_search_track: ListState::default(),
+
+            selected_playlist: ListState::default(),
+            selected_playlist_track: ListState::default(),
+
+            selected_download: ListState::default(),
+
+            selected_session: ListState::default(),
+
+            favorite_tracks: vec![],
+            selected_favorite: ListState::default(),
+
+            dashboard_section: DashboardSection::default(),
+            recently_added_albums: vec![],
+            recently_played_tracks: vec![],
+            most_played_tracks: vec![],
+            favorite_albums: vec![],
+            selected_recently_added: ListState::default(),
+            selected_recently_played: ListState::default(),
+            selected_most_played: ListState::default(),
+            selected_favorite_album: ListState::default(),
+
+            genres_section: GenresSection::default(),
+            genres: vec![],
+            genre_artists: vec![],
+            genre_albums: vec![],
+            open_genre_id: String::from(""),
+            selected_genre: ListState::default(),
+            selected_genre_artist: ListState::default(),
+            selected_genre_album: ListState::default(),
+
+            albums_section: AlbumsSection::default(),
+            albums: vec![],
+            album_sort: AlbumSort::default(),
+            album_tracks: vec![],
+            open_album_id: String::from(""),
+            selected_album: ListState::default(),
+            selected_album_track: ListState::default(),
+
             client: None,
-            mpv_thread: None,
-            mpv_state: Arc::new(Mutex::new(MpvState::new())),
-            sender,
+            player: Player::new(sender),
             receiver,
             current_playback_state: MpvPlaybackState {
                 percentage: 0.0,
                 duration: 0.0,
                 current_index: 0,
-                last_index: -1,
                 volume: 100,
+                commands: 0,
             },
             old_percentage: 0.0,
             scrobble_this: (String::from(""), 0),
+            playing_unreported: false,
             controls,
-        }
-    }
-}
 
-pub struct MpvState {
-    pub mpris_events: Vec<MediaControlEvent>,
-    pub mpv: Mpv,
-    pub should_stop: bool,
-}
+            last_reconnect: Instant::now(),
+            reconnect_sender,
+            reconnect_receiver,
 
-impl MpvState {
-    fn new() -> Self {
-        let mpv = Mpv::new().expect("[XX] Failed to create mpv instance");
-        mpv.set_property("vo", "null").unwrap();
-        mpv.set_property("volume", 100).unwrap();
-        mpv.set_property("prefetch-playlist", "yes").unwrap(); // gapless playback
-
-        let ev_ctx = mpv.create_event_context();
-        ev_ctx.disable_deprecated_events().unwrap();
-        ev_ctx.observe_property("volume", Format::Int64, 0).unwrap();
-        ev_ctx
-            .observe_property("demuxer-cache-state", Format::Node, 0)
-            .unwrap();
-        MpvState {
-            mpris_events: vec![],
-            mpv,
-            should_stop: false,
+            library_updates_sender,
+            library_updates,
+
+            remote_commands_sender,
+            remote_commands,
+
+            mpris_events_sender,
+            mpris_events,
+
+            sessions: vec![],
+            last_sessions_refresh: Instant::now(),
+            sessions_sender,
+            sessions_updates,
+
+            syncplay: None,
+            syncplay_groups: vec![],
+
+            auto_dj: false,
+            auto_dj_seed: String::from(""),
+            auto_dj_sender,
+            auto_dj_updates,
         }
     }
 }
@@ -236,30 +573,52 @@ impl MpvState {
 impl App {
     pub async fn init(&mut self, artists: Vec<Artist>) {
         let client = client::Client::new().await;
-        if client.access_token.is_empty() {
+        if client.access_token.is_empty() && !client.offline {
             println!("Failed to authenticate. Exiting...");
             return;
         }
+        self.track_columns = TrackColumn::ALL
+            .into_iter()
+            .filter(|column| client.track_columns.iter().any(|name| TrackColumn::from_config(name) == Some(*column)))
+            .collect();
         self.client = Some(client);
         self.artists = artists;
         self.active_section = ActiveSection::Artists;
         self.selected_artist.select(Some(0));
 
-        self.register_controls(self.mpv_state.clone());
+        self.register_controls();
+
+        self.init_downloads();
+        self.init_remote();
+        self.restore_queue();
     }
 
     pub async fn run<'a>(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
-        // get playback state from the mpv thread
-        let state = self.receiver.try_recv()?;
+        self.handle_download_events();
+        self.handle_library_updates();
+        self.handle_remote_commands();
+        self.handle_sessions_updates();
+        self.handle_auto_dj_updates();
+        if self.active_tab == ActiveTab::Sessions {
+            self.refresh_sessions(false);
+        }
+        self.try_reconnect().await;
+
+        // get playback state from the player thread, only the latest one matters
+        let mut state = self.receiver.try_recv()?;
+        while let Ok(newer) = self.receiver.try_recv() {
+            state = newer;
+        }
+        // sampled before the player got to our last command, e.g. a queue edit
+        if state.commands < self.player.sent() {
+            return Ok(());
+        }
 
         self.current_playback_state.percentage = state.percentage;
         self.current_playback_state.current_index = state.current_index;
         self.current_playback_state.duration = state.duration;
         self.current_playback_state.volume = state.volume;
-
-        // Queue position
-        self.selected_queue_item
-            .select(Some(state.current_index as usize));
+        self.top_up_auto_dj();
 
         let song = self.playlist.get(state.current_index as usize).cloned().unwrap_or_default();
 
@@ -281,9 +640,11 @@ impl App {
             let client = self.client.as_ref().ok_or("[!!] No client")?;
 
             let runit = report_progress(
-                client.base_url.clone(), client.access_token.clone(), ProgressReport {
+                client.base_url.clone(), client.access_token.clone(), client.device_id.clone(), ProgressReport {
                 volume_level: self.current_playback_state.volume as u64,
                 is_paused: self.paused,
+                repeat_mode: self.repeat.as_jellyfin_str().to_string(),
+                shuffle_mode: if self.shuffle { "Shuffle" } else { "Sorted" }.to_string(),
                 // take into account duratio, percentage and *10000
                 position_ticks: (self.current_playback_state.duration * self.current_playback_state.percentage * 100000.0) as u64,
                 media_source_id: self.active_song_id.clone(),
@@ -303,6 +664,12 @@ impl App {
             self.selected_lyric_manual_override = false;
             self.active_song_id = song.id.clone();
 
+            // Queue position, unless the user is busy editing the queue
+            if self.active_section != ActiveSection::Queue {
+                self.selected_queue_item
+                    .select(Some(state.current_index as usize));
+            }
+
             // fetch lyrics
             let client = self.client.as_ref().ok_or("[!!] No client")?;
             let lyrics = client.lyrics(self.active_song_id.clone()).await;
@@ -315,21 +682,26 @@ impl App {
 
             self.selected_lyric.select(None);
 
-            self.cover_art = None;
-            let cover_image = client.download_cover_art(song.parent_id).await.unwrap_or_default();
-            
-            if !cover_image.is_empty() && !self.cover_art_dir.is_empty() {
-                // let p = format!("./covers/{}", cover_image);
-                let p = format!("{}/{}", self.cover_art_dir, cover_image);
-                if let Ok(reader) = image::ImageReader::open(p) {
-                    if let Ok(img) = reader.decode() {
-                        if let Some(ref mut picker) = self.picker {
-                            let image_fit_state = picker.new_resize_protocol(img.clone());
-                            self.cover_art = Some(image_fit_state);
+            let cover_art_key = (song.parent_id.clone(), song.album_image_tag.clone());
+            if cover_art_key != self.cover_art_key {
+                self.cover_art = None;
+                let cover_image = match cache::load_cover(&song.parent_id, &song.album_image_tag) {
+                    Some(bytes) => Some(bytes),
+                    None => match client.download_cover_art(&song.parent_id, &song.album_image_tag).await {
+                        Ok(bytes) => {
+                            cache::save_cover(&song.parent_id, &song.album_image_tag, &bytes);
+                            Some(bytes)
                         }
+                        Err(_) => None,
+                    },
+                };
+                if let Some(Ok(img)) = cover_image.map(|bytes| image::load_from_memory(&bytes)) {
+                    if let Some(ref mut picker) = self.picker {
+                        self.cover_art = Some(picker.new_resize_protocol(img));
                     }
                 }
-            };
+                self.cover_art_key = cover_art_key;
+            }
 
             if self.scrobble_this.0 != "" {
                 let _ = client.stopped(
@@ -339,8 +711,23 @@ impl App {
                 self.scrobble_this = (String::from(""), 0);
             }
 
-            let _ = client.playing(self.active_song_id.clone()).await;
+            // a paused song is not playing yet, the server hears about it once it resumes
+            self.playing_unreported = self.paused;
+            if !self.paused {
+                let _ = client.playing(self.active_song_id.clone()).await;
+            }
+
+            self.save_queue();
         }
+
+        if self.playing_unreported && !self.paused {
+            self.playing_unreported = false;
+            if let Some(client) = self.client.as_ref() {
+                let _ = client.playing(self.active_song_id.clone()).await;
+            }
+        }
+
+        self.update_mpris();
         Ok(())
     }
 
@@ -387,6 +774,31 @@ impl App {
             ActiveTab::Search => {
                 self.render_search(app_container[1], frame);
             }
+            ActiveTab::Playlists => {
+                self.render_playlists(app_container[1], frame);
+            }
+            ActiveTab::Downloads => {
+                self.render_downloads(app_container[1], frame);
+            }
+            ActiveTab::Sessions => {
+                self.render_sessions(app_container[1], frame);
+            }
+            ActiveTab::Favorites => {
+                self.render_favorites(app_container[1], frame);
+            }
+            ActiveTab::Dashboard => {
+                self.render_dashboard(app_container[1], frame);
+            }
+            ActiveTab::Genres => {
+                self.render_genres(app_container[1], frame);
+            }
+            ActiveTab::Albums => {
+                self.render_albums(app_container[1], frame);
+            }
+        }
+
+        if self.popup.is_some() {
+            self.render_popup(frame);
         }
 
         self.spinner_skipped += 1;
@@ -408,7 +820,7 @@ impl App {
                 Constraint::Percentage(20),
             ])
             .split(area);
-        Tabs::new(vec!["Library", "Search"])
+        Tabs::new(vec!["Library", "Search", "Playlists", "Downloads", "Sessions", "Favorites", "Dashboard", "Genres", "Albums"])
             .style(Style::default().white())
             .highlight_style(Style::default().blue())
             .select(self.active_tab as usize)
@@ -423,8 +835,28 @@ impl App {
         } else {
             Color::Yellow
         };
-        Paragraph::new(volume)
-            .style(Style::default().fg(volume_color))
+        let offline = if self.client.as_ref().is_some_and(|client| client.offline) {
+            "Offline  ".red().bold()
+        } else {
+            Span::from("")
+        };
+        let transcoding = match self.client.as_ref() {
+            Some(client) if client.transcoding.enabled => format!(
+                "Transcoding: {} {} kbps  ",
+                client.transcoding.codec, client.transcoding.bitrate
+            ),
+            _ => String::from(""),
+        };
+        let syncplay = match self.syncplay.as_ref() {
+            Some(group) => format!("SyncPlay: {} ({})  ", group.info.name, group.info.participants.len()),
+            None => String::from(""),
+        };
+        Paragraph::new(Line::from(vec![
+            offline,
+            Span::styled(syncplay, Style::default().fg(Color::Blue)),
+            Span::styled(transcoding, Style::default().fg(Color::DarkGray)),
+            Span::styled(volume, Style::default().fg(volume_color)),
+        ]))
             .alignment(Alignment::Right)
             .wrap(Wrap { trim: false })
             .render(tabs_layout[1], buf);
@@ -433,110 +865,584 @@ impl App {
     /// Fetch the discography of an artist
     /// This will change the active section to tracks
     pub async fn discography(&mut self, id: &str) {
+        self.open_artist_id = id.to_string();
         if let Some(client) = self.client.as_ref() {
+            // a cached copy is shown right away and refreshed in the background, for the play counts
+            // and favorites. If the server changed since, songs may be missing, so wait for it instead
+            if let Some((discography, fresh)) = cache::load_discography(id).filter(|_| !client.offline) {
+                let discography = if fresh {
+                    discography
+                } else {
+                    match client.discography(id).await {
+                        Ok(fetched) if !fetched.items.is_empty() => fetched,
+                        _ => discography,
+                    }
+                };
+                self.active_section = ActiveSection::Tracks;
+                self.tracks = discography.items;
+                self.favorite_album_ids = discography.favorite_album_ids;
+                if fresh {
+                    self.refresh_discography(id);
+                }
+                return;
+            }
             let artist = client.discography(id).await;
             if let Ok(artist) = artist {
                 self.active_section = ActiveSection::Tracks;
                 self.tracks = artist.items;
+                self.favorite_album_ids = artist.favorite_album_ids;
             }
         }
     }
 
-    pub fn replace_playlist(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
-        if let Some(thread) = &self.mpv_thread {
-            if thread.is_finished() {
-                self.mpv_thread = None;
-            } else {
-                if let Some(thread) = self.mpv_thread.take() {
-                    thread.join().map_err(|e| format!("Failed to join thread: {:?}", e))?;
+    /// Fetch an artist's discography again without blocking the ui. It replaces the track list if
+    /// the artist is still open by then
+    pub fn refresh_discography(&self, id: &str) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client.clone(),
+            _ => return,
+        };
+        let sender = self.library_updates_sender.clone();
+        let id = id.to_string();
+        tokio::spawn(async move {
+            // a failed request comes back empty
+            if let Some(discography) = client.discography(&id).await.ok().filter(|d| !d.items.is_empty()) {
+                let _ = sender.send(LibraryUpdate::Discography(id, discography));
+            }
+        });
+    }
+
+    /// Check the metadata cache against the server and reload the artists, without blocking the ui
+    pub fn refresh_library(&self) {
+        let client = match self.client.as_ref() {
+            Some(client) if !client.offline => client.clone(),
+            _ => return,
+        };
+        let sender = self.library_updates_sender.clone();
+        tokio::spawn(async move {
+            if let Ok(date_created) = client.latest_date_created().await {
+                cache::validate(&client.base_url, &date_created);
+            }
+            if let Ok(artists) = client.library_artists().await {
+                if !artists.is_empty() {
+                    let _ = sender.send(LibraryUpdate::Artists(artists));
+                }
+            }
+        });
+    }
+
+    /// Swap in refreshed data, keeping the cursors on the same artist and track
+    fn handle_library_updates(&mut self) {
+        while let Ok(update) = self.library_updates.try_recv() {
+            match update {
+                LibraryUpdate::Artists(artists) => {
+                    let selected_id = self
+                        .artists
+                        .get(self.selected_artist.selected().unwrap_or(0))
+                        .map(|artist| artist.id.clone());
+                    self.artists = artists;
+                    if self.artists_search_term.is_empty() {
+                        let index = self.artists.iter().position(|artist| Some(&artist.id) == selected_id.as_ref());
+                        self.selected_artist.select(Some(index.unwrap_or(0)));
+                    }
+                }
+                LibraryUpdate::Discography(id, discography) => {
+                    if id != self.open_artist_id {
+                        continue;
+                    }
+                    let selected = self.selected_track_row().map(|row| self.track_row_key(row));
+                    self.tracks = discography.items;
+                    self.favorite_album_ids = discography.favorite_album_ids;
+                    if let Some((album_id, song_id)) = selected {
+                        self.select_track_row(&album_id, &song_id);
+                    }
                 }
             }
         }
+    }
 
-        self.mpv_state = Arc::new(Mutex::new(MpvState::new())); // shared state for controlling MPV
-        let mpv_state = self.mpv_state.clone();
-        let sender = self.sender.clone();
-        let songs = self.playlist.clone();
-        // println!("Playing playlist: {:?}", songs);
+    /// The rows of the track list: album headers and the songs of expanded albums,
+    /// or only the matching songs while searching
+    pub fn track_rows(&self) -> Vec<TrackRow> {
+        let mut rows = vec![];
+        if !self.tracks_search_term.is_empty() {
+            let term = self.tracks_search_term.to_lowercase();
+            for (i, track) in self.tracks.iter().enumerate() {
+                if track.name.to_lowercase().contains(&term) {
+                    rows.push(TrackRow::Song(i));
+                }
+            }
+            return rows;
+        }
+        for (i, track) in self.tracks.iter().enumerate() {
+            if i == 0 || self.tracks[i - 1].album_id != track.album_id {
+                rows.push(TrackRow::Album(i));
+            }
+            if !self.collapsed_albums.contains(&track.album_id) {
+                rows.push(TrackRow::Song(i));
+            }
+        }
+        rows
+    }
+
+    pub fn selected_track_row(&self) -> Option<TrackRow> {
+        self.track_rows().get(self.selected_track.selected()?).copied()
+    }
+
+    /// The songs of the album that starts at `first`
+    pub fn album_songs(&self, first: usize) -> &[DiscographySong] {
+        let album_id = match self.tracks.get(first) {
+            Some(track) => &track.album_id,
+            None => return &[],
+        };
+        let len = self.tracks[first..].iter().take_while(|track| &track.album_id == album_id).count();
+        &self.tracks[first..first + len]
+    }
+
+    /// The album id, and song id for songs, that identify a row across reloads
+    pub fn track_row_key(&self, row: TrackRow) -> (String, String) {
+        match row {
+            TrackRow::Album(i) => (self.tracks[i].album_id.clone(), String::from("")),
+            TrackRow::Song(i) => (self.tracks[i].album_id.clone(), self.tracks[i].id.clone()),
+        }
+    }
+
+    /// Put the cursor on a song, or on its album when the song is hidden or not given
+    pub fn select_track_row(&mut self, album_id: &str, song_id: &str) {
+        let rows = self.track_rows();
+        let song = rows.iter().position(|row| matches!(row, TrackRow::Song(i) if self.tracks[*i].id == song_id));
+        let album = rows.iter().position(|row| matches!(row, TrackRow::Album(i) if self.tracks[*i].album_id == album_id));
+        let index = song.or(album).or(self.selected_track.selected()).filter(|index| *index < rows.len());
+        self.selected_track.select(if rows.is_empty() { None } else { Some(index.unwrap_or(0)) });
+    }
+
+    /// Fetch the user's playlists
+    pub async fn refresh_playlists(&mut self) {
+        if let Some(client) = self.client.as_ref() {
+            if let Ok(playlists) = client.playlists().await {
+                self.playlists = playlists;
+            }
+        }
+        let selected = self.selected_playlist.selected().unwrap_or(0);
+        if self.playlists.is_empty() {
+            self.selected_playlist.select(None);
+        } else {
+            self.selected_playlist.select(Some(selected.min(self.playlists.len() - 1)));
+        }
+    }
+
+    /// Fetch the songs of a playlist
+    /// This will change the active playlists section to tracks
+    pub async fn open_playlist(&mut self, id: &str) {
+        if let Some(client) = self.client.as_ref() {
+            if let Ok(tracks) = client.playlist(id).await {
+                self.playlists_section = PlaylistsSection::Tracks;
+                self.open_playlist_id = id.to_string();
+                self.playlist_tracks = tracks;
+                self.selected_playlist_track.select(if self.playlist_tracks.is_empty() { None } else { Some(0) });
+            }
+        }
+    }
+
+    pub fn replace_playlist(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.replace_playlist_from(0)
+    }
+
+    /// Replace the queue with `self.playlist` and play it from the song at `start`.
+    /// The songs before it stay in the queue, so previous can reach them
+    pub fn replace_playlist_from(&mut self, start: usize) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let start = start.min(self.playlist.len().saturating_sub(1));
+        // the song at `start` is the one the user picked, the rest after it get shuffled
+        if self.shuffle && self.playlist.len() > start + 2 {
+            self.playlist[start + 1..].shuffle(&mut rand::thread_rng());
+        }
+        // in a group, the queue starts once the server hands it to everyone
+        if self.syncplay_set_queue(start) {
+            return Ok(());
+        }
 
         let state: MpvPlaybackState = MpvPlaybackState {
             percentage: 0.0,
             duration: 0.0,
-            current_index: 0,
-            last_index: -1,
+            current_index: start as i64,
             volume: self.current_playback_state.volume,
+            commands: 0,
         };
+        self.start_playlist(state, false)
+    }
+
+    /// Plays `self.playlist`, from `state.current_index` and `state.percentage`
+    pub fn start_playlist(&mut self, state: MpvPlaybackState, paused: bool) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let files = self.player_files(&self.playlist);
+        self.player.send(PlayerCommand::SetProperty("volume", state.volume.into()));
+        self.player.send(PlayerCommand::Load {
+            files,
+            index: state.current_index,
+            percentage: state.percentage,
+            paused,
+        });
+        self.current_playback_state.current_index = state.current_index;
+
+        self.apply_repeat()?;
+
+        self.paused = paused;
+
+        Ok(())
+    }
+
+    /// Per-file mpv options of a song, comma separated: its normalization gain
+    fn file_options(&self, song: &Song) -> String {
+        let gain = self
+            .client
+            .as_ref()
+            .and_then(|client| client.normalization.gain(song.normalization_gain, song.album_normalization_gain));
+        match gain {
+            Some(gain) => format!("volume-gain={:.2}", gain),
+            None => String::from(""),
+        }
+    }
+
+    /// The songs as the player loads them
+    fn player_files(&self, songs: &[Song]) -> Vec<PlayerFile> {
+        songs
+            .iter()
+            .map(|song| PlayerFile { url: song.url.clone(), options: self.file_options(song) })
+            .collect()
+    }
+
+    /// Is there an mpv playlist we can add songs to?
+    fn is_playing(&self) -> bool {
+        !self.playlist.is_empty()
+    }
+
+    /// Add songs to the end of the queue without interrupting playback
+    /// If nothing is playing, the songs become the new queue
+    pub fn append_to_playlist(&mut self, songs: Vec<Song>) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if songs.is_empty() || self.syncplay_enqueue(&songs, false) {
+            return Ok(());
+        }
+        if !self.is_playing() {
+            self.playlist = songs;
+            return self.replace_playlist();
+        }
+
+        let files = self.player_files(&songs);
+        self.player.send(PlayerCommand::Append(files));
+
+        self.playlist.extend(songs);
+        Ok(())
+    }
+
+    /// Insert songs right after the current song without interrupting playback
+    /// If nothing is playing, the songs become the new queue
+    pub fn push_next_to_playlist(&mut self, songs: Vec<Song>) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if songs.is_empty() || self.syncplay_enqueue(&songs, true) {
+            return Ok(());
+        }
+        if !self.is_playing() {
+            self.playlist = songs;
+            return self.replace_playlist();
+        }
+
+        let insert_at = (self.current_playback_state.current_index.max(0) as usize + 1).min(self.playlist.len());
+
+        let files = self.player_files(&songs);
+        self.player.send(PlayerCommand::Insert(files, insert_at));
+
+        self.playlist.splice(insert_at..insert_at, songs);
+        Ok(())
+    }
+
+    /// Play the queue entry at `index`
+    pub fn playlist_jump(&mut self, index: usize) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if index >= self.playlist.len() || self.syncplay_jump(index) {
+            return Ok(());
+        }
+        self.player.send(PlayerCommand::Jump(index));
+        self.player.send(PlayerCommand::SetProperty("pause", false.into()));
+
+        self.paused = false;
+        self.buffering = 1;
+        self.sync_current_index(index as i64);
+        Ok(())
+    }
+
+    /// Remove the queue entry at `index`. Removing the current song skips to the next one
+    pub fn playlist_remove(&mut self, index: usize) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if index >= self.playlist.len() || self.syncplay_remove(index..index + 1) {
+            return Ok(());
+        }
+        self.player.send(PlayerCommand::Remove(index));
+
+        self.playlist.remove(index);
+        let current = self.current_playback_state.current_index;
+        if (index as i64) < current {
+            self.sync_current_index(current - 1);
+        } else {
+            self.sync_current_index(current);
+        }
+        Ok(())
+    }
+
+    /// Move the queue entry at `from` so that it ends up at index `to`
+    pub fn playlist_move(&mut self, from: usize, to: usize) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if from >= self.playlist.len() || to >= self.playlist.len() || from == to || self.syncplay_move(from, to) {
+            return Ok(());
+        }
+        // mpv inserts the entry in front of the target index, which is one further when moving down
+        let target = if to > from { to + 1 } else { to };
+        self.player.send(PlayerCommand::Move(from, target));
+
+        let song = self.playlist.remove(from);
+        self.playlist.insert(to, song);
+
+        let current = self.current_playback_state.current_index;
+        let (from, to) = (from as i64, to as i64);
+        if from == current {
+            self.sync_current_index(to);
+        } else if from < current && to >= current {
+            self.sync_current_index(current - 1);
+        } else if from > current && to <= current {
+            self.sync_current_index(current + 1);
+        }
+        Ok(())
+    }
 
+    /// Remove every queue entry after the current song
+    pub fn playlist_clear_after_current(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let keep = (self.current_playback_state.current_index.max(0) + 1) as usize;
+        if keep >= self.playlist.len() || self.syncplay_remove(keep..self.playlist.len()) {
+            return Ok(());
+        }
+        for index in (keep..self.playlist.len()).rev() {
+            self.player.send(PlayerCommand::Remove(index));
+        }
+
+        self.playlist.truncate(keep);
+        Ok(())
+    }
+
+    pub fn toggle_shuffle(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.set_shuffle(!self.shuffle)
+    }
+
+    /// Turn shuffle on or off. Turning it on shuffles everything after the current song
+    pub fn set_shuffle(&mut self, shuffle: bool) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if shuffle == self.shuffle {
+            return Ok(());
+        }
+        self.shuffle = shuffle;
         if let Some(ref mut controls) = self.controls {
-            if let Ok(_) = controls.detach() {
-                self.register_controls(mpv_state.clone());
-            }
+            let _ = controls.set_shuffle(self.shuffle);
+        }
+        if self.shuffle && self.is_playing() {
+            self.shuffle_after_current()?;
         }
+        Ok(())
+    }
 
-        self.mpv_thread = Some(thread::spawn(move || {
-            if let Err(e) = Self::t_playlist(songs, mpv_state, sender, state) {
-                eprintln!("Error in playlist thread: {:?}", e);
+    /// Randomly reorder the queue after the current song, keeping the current song playing
+    fn shuffle_after_current(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let start = (self.current_playback_state.current_index.max(0) + 1) as usize;
+        if start + 1 >= self.playlist.len() {
+            return Ok(());
+        }
+        let mut wanted = (start..self.playlist.len()).collect::<Vec<usize>>();
+        wanted.shuffle(&mut rand::thread_rng());
+
+        // `order[i]` is the original index of the song now at position `i`
+        let mut order = (0..self.playlist.len()).collect::<Vec<usize>>();
+        for (offset, original) in wanted.iter().enumerate() {
+            let to = start + offset;
+            let from = order.iter().position(|i| i == original).unwrap_or(to);
+            if from != to {
+                // always moving up, so the entry lands exactly at `to`
+                self.player.send(PlayerCommand::Move(from, to));
+                let moved = order.remove(from);
+                order.insert(to, moved);
             }
-        }));
+        }
 
-        self.paused = false;
+        let playlist = order.iter().map(|&i| self.playlist[i].clone()).collect();
+        self.playlist = playlist;
+        Ok(())
+    }
+
+    /// Cycle through repeat off, repeat all and repeat one
+    pub fn cycle_repeat(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.set_repeat(self.repeat.next())
+    }
+
+    pub fn set_repeat(&mut self, repeat: Repeat) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.repeat = repeat;
+        if let Some(ref mut controls) = self.controls {
+            let _ = controls.set_loop_status(self.repeat.into());
+        }
+        self.apply_repeat()
+    }
 
+    /// Push the repeat mode to mpv
+    fn apply_repeat(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let (loop_playlist, loop_file) = match self.repeat {
+            Repeat::None => ("no", "no"),
+            Repeat::All => ("inf", "no"),
+            Repeat::One => ("no", "inf"),
+        };
+        self.player.send(PlayerCommand::SetProperty("loop-playlist", loop_playlist.into()));
+        self.player.send(PlayerCommand::SetProperty("loop-file", loop_file.into()));
         Ok(())
     }
 
-    /// The thread that keeps in sync with the mpv thread
-    fn t_playlist(
-        songs: Vec<Song>,
-        mpv_state: Arc<Mutex<MpvState>>,
-        sender: Sender<MpvPlaybackState>,
-        state: MpvPlaybackState,
-    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
-        let mpv = mpv_state.lock().map_err(|e| format!("Failed to lock mpv_state: {:?}", e))?;
+    /// Swap the queue for `songs` without interrupting the current song, which becomes `songs[current]`
+    pub fn replace_queue_around_current(&mut self, songs: Vec<Song>, current: usize) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if !self.is_playing() || current >= songs.len() {
+            return Err("Nothing is playing".into());
+        }
+        let before = self.player_files(&songs[..current]);
+        let after = self.player_files(&songs[current + 1..]);
+        self.player.send(PlayerCommand::ReplaceAround { before, after });
 
-        let _ = mpv.mpv.playlist_clear();
+        self.playlist = songs;
+        self.sync_current_index(current as i64);
+        Ok(())
+    }
 
-        let files = songs
-            .iter()
-            .map(|song| (song.url.as_str(), FileState::AppendPlay, None))
-            .collect::<Vec<_>>();
-
-        mpv.mpv
-            .playlist_load_files(&files)
-            .map_err(|e| format!("Failed to load playlist: {:?}", e))?;
-
-        mpv.mpv.set_property("volume", state.volume)?;
-
-        drop(mpv);
-
-        loop {
-            // main mpv loop
-            let mpv = mpv_state.lock().map_err(|e| format!("Failed to lock mpv_state: {:?}", e))?;
-
-            if mpv.should_stop {
-                return Ok(());
-            }
-            let percentage = mpv.mpv.get_property("percent-pos").unwrap_or(0.0);
-            let current_index: i64 = mpv.mpv.get_property("playlist-pos").unwrap_or(0);
-            let duration = mpv.mpv.get_property("duration").unwrap_or(0.0);
-            let volume = mpv.mpv.get_property("volume").unwrap_or(0);
-            drop(mpv);
-
-            let _ = sender
-                .send({
-                    MpvPlaybackState {
-                        percentage,
-                        duration,
-                        current_index,
-                        last_index: state.last_index,
-                        volume: volume as i64,
-                    }
-                });
+    /// After editing the queue, the player's states are stale until it handles the edit,
+    /// so the index is moved here, and a shifted index is not mistaken for a song change
+    fn sync_current_index(&mut self, index: i64) {
+        self.current_playback_state.current_index = index;
+    }
+
+    /// While offline, try to reach the server every 30 seconds without blocking the ui.
+    /// Once it is back, the recorded playback events are replayed and the full library is loaded
+    async fn try_reconnect(&mut self) {
+        if let Ok(client) = self.reconnect_receiver.try_recv() {
+            self.client = Some(client);
+            self.init_downloads(); // restart the worker with the authenticated client
+            self.init_remote();
+            self.refresh_library();
+            return;
+        }
 
-            thread::sleep(Duration::from_secs_f32(0.2));
+        let mut client = match &self.client {
+            Some(client) if client.offline => client.clone(),
+            _ => return,
+        };
+        if self.last_reconnect.elapsed() < Duration::from_secs(30) {
+            return;
         }
+        self.last_reconnect = Instant::now();
+        let sender = self.reconnect_sender.clone();
+        tokio::spawn(async move {
+            if client.reconnect().await {
+                client.replay_pending_events().await;
+                let _ = sender.send(client);
+            }
+        });
+    }
+
+    /// Where the queue is saved, next to `seen_artists`
+    fn queue_file() -> Option<PathBuf> {
+        cache_dir().map(|dir| dir.join("jellyfin-tui").join("queue.json"))
+    }
+
+    /// Save the queue, the current song, the position in it and the volume to the cache dir
+    pub fn save_queue(&self) {
+        let file = match Self::queue_file() {
+            Some(file) => file,
+            None => return,
+        };
+        let saved = SavedQueue {
+            playlist: self.playlist.clone(),
+            current_index: self.current_playback_state.current_index,
+            percentage: self.current_playback_state.percentage,
+            volume: self.current_playback_state.volume,
+        };
+        if let Ok(json) = serde_json::to_string(&saved) {
+            if let Some(dir) = file.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            let _ = std::fs::write(file, json);
+        }
+    }
+
+    /// Load the queue saved by the last session, paused where it was left off
+    fn restore_queue(&mut self) {
+        let saved = match Self::queue_file()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .and_then(|json| serde_json::from_str::<SavedQueue>(&json).ok())
+        {
+            Some(saved) => saved,
+            None => return,
+        };
+        let client = match self.client.as_ref() {
+            Some(client) => client,
+            None => return,
+        };
+
+        self.current_playback_state.volume = saved.volume;
+
+        let mut saved = saved;
+        if client.offline {
+            // only downloaded songs can be played, resume the current one only if it is among them
+            let current_id = saved.playlist.get(saved.current_index.max(0) as usize).map(|song| song.id.clone());
+            saved.playlist.retain(|song| downloads::downloaded_file(&song.id).is_some());
+            match current_id.and_then(|id| saved.playlist.iter().position(|song| song.id == id)) {
+                Some(index) => saved.current_index = index as i64,
+                None => {
+                    saved.current_index = 0;
+                    saved.percentage = 0.0;
+                }
+            }
+        }
+
+        if saved.playlist.is_empty() {
+            return;
+        }
+        self.playlist = saved
+            .playlist
+            .into_iter()
+            .map(|song| Song {
+                url: client.song_url_sync(song.id.clone()),
+                ..song
+            })
+            .collect();
+        let current_index = saved.current_index.clamp(0, self.playlist.len() as i64 - 1);
+        self.selected_queue_item.select(Some(current_index as usize));
+
+        let state = MpvPlaybackState {
+            percentage: saved.percentage,
+            duration: 0.0,
+            current_index,
+            volume: saved.volume,
+            commands: 0,
+        };
+        let _ = self.start_playlist(state, true);
     }
 
     pub fn exit(&mut self) {
+        self.save_queue();
         self.exit = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_columns_from_config_names() {
+        assert_eq!(TrackColumn::from_config("play_count"), Some(TrackColumn::PlayCount));
+        assert_eq!(TrackColumn::from_config("played"), Some(TrackColumn::Played));
+        assert_eq!(TrackColumn::from_config("favorite"), Some(TrackColumn::Favorite));
+        assert_eq!(TrackColumn::from_config("last_played"), Some(TrackColumn::LastPlayed));
+        assert_eq!(TrackColumn::from_config("genre"), Some(TrackColumn::Genre));
+        assert_eq!(TrackColumn::from_config("codec"), Some(TrackColumn::Codec));
+    }
+
+    #[test]
+    fn unknown_track_columns_are_ignored() {
+        assert_eq!(TrackColumn::from_config("Play_Count"), None);
+        assert_eq!(TrackColumn::from_config(""), None);
+    }
+}